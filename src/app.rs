@@ -1,192 +1,85 @@
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
-use std::{
-    io::{BufRead, BufReader},
-    process::{Command, Stdio},
-    sync::{Arc, atomic::Ordering, mpsc::channel},
-    thread,
-};
+use std::sync::Arc;
 
-use dirs::video_dir;
 use iced::{
-    Font, Length, Settings, Task,
-    widget::{Space, column, container, progress_bar, row, stack, text},
+    Font, Length, Settings, Subscription, Task,
+    widget::{Column, Space, column, container, progress_bar, row, stack, text},
 };
-use itertools::Itertools;
-use notify_rust::Notification;
-use regex::Regex;
 use reqwest::Client;
-use tokio::runtime::Handle;
 
 use crate::{
+    catalogue_cache,
     config::Config,
-    download::{Download, DownloadToken},
-    episodes_page::{self, WHITELIST},
+    download::{Command, Download, DownloadToken},
+    downloads_page, episodes_page, history_page,
+    http_client::HttpClient,
     list_query_state::ListQueryState,
     main_menu_page::{self, MainMenuPage},
+    notifications::{self, Action},
     options_page,
     page::{AppUpdate, Page},
     presets::square_box,
-    search_page,
+    search_page, storage, storage_page,
 };
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Update,
     Download(Vec<DownloadToken>),
+    Config(Box<Config>),
     MainMenu(main_menu_page::Message),
     Options(options_page::Message),
     Search(search_page::Message),
     Episodes(episodes_page::Message),
+    Downloads(downloads_page::Message),
+    Storage(storage_page::Message),
+    History(history_page::Message),
+    Notification(notifications::Action),
 }
 
 pub struct App {
     pub page: Box<dyn Page>,
+    pub client: HttpClient,
     pub download: Arc<Download>,
 }
 
 impl Default for App {
-    #[allow(clippy::too_many_lines)]
     fn default() -> Self {
         let config: Config = Config::init().expect("Couldn't initialize config");
         let config2 = config.clone();
 
-        let client = Client::builder()
-            .user_agent(
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:100.0) Gecko/20100101 Firefox/100.0",
-            )
-            .cookie_store(true)
-            .build()
-            .unwrap_or_else(|err| {
-                eprintln!("{err}");
-                Client::default()
-            });
+        let client = HttpClient::new(
+            Client::builder()
+                .user_agent(
+                    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:100.0) Gecko/20100101 Firefox/100.0",
+                )
+                .cookie_store(true)
+                .build()
+                .unwrap_or_else(|err| {
+                    eprintln!("{err}");
+                    Client::default()
+                }),
+            config.http.clone(),
+        );
         let client2 = client.clone();
 
         let scraper = config.scraper;
 
-        let anime_list = ListQueryState::spawn(scraper, client.clone());
-
-        let (tx, rx) = channel();
-
-        let download = Arc::new(Download::new(tx));
-        let download2 = download.clone();
-
-        let progress_re = Regex::new(r"([0-9.].*)%").unwrap();
-
-        let handle = Handle::current();
-
-        thread::spawn(move || {
-            while let Ok(download_token) = rx.recv() {
-                download.progress().store(0.0, Ordering::Relaxed);
-                *download.current() = Some(download_token.clone());
-
-                let DownloadToken {
-                    name,
-                    slug,
-                    episode,
-                } = download_token;
-
-                let mirrors = handle.block_on(async {
-                    config
-                        .scraper
-                        .try_get_mirrors(&client, &slug, episode)
-                        .await
-                        .expect("Couldn't get mirrors")
-                });
-
-                let mirrors = mirrors
-                    .into_iter()
-                    .filter(|mirror| WHITELIST.iter().any(|elem| mirror.contains(elem)))
-                    .collect_vec();
+        let anime_list = ListQueryState::spawn(scraper, client.clone(), config.catalogue_ttl());
 
-                let _ = Notification::new()
-                    .summary("Ani-link")
-                    .body(format!(r"Descargando episodio {episode} de {name}...").as_str())
-                    .show()
-                    .is_ok();
-
-                let success = mirrors.into_iter().any(|mirror| {
-                    let mut command = Command::new(format!(
-                        "yt-dlp{}",
-                        if cfg!(target_os = "windows") {
-                            ".exe"
-                        } else {
-                            ""
-                        }
-                    ));
-
-                    #[cfg(target_os = "windows")]
-                    command.creation_flags(0x08000000);
-
-                    let mut process = command
-                        .arg(mirror)
-                        .arg("--no-check-certificates")
-                        .arg("--newline")
-                        .arg("--output")
-                        .arg(format!(
-                            "{}/ani-link/{slug}/{slug}-{episode}.%(ext)s",
-                            video_dir()
-                                .expect("Video path not found")
-                                .into_os_string()
-                                .into_string()
-                                .expect("Video path could not be converted to string"),
-                        ))
-                        .stdout(Stdio::piped())
-                        .stderr(Stdio::null())
-                        .spawn()
-                        .expect("couldn't run yt-dlp");
-
-                    let stdout = process.stdout.as_mut().unwrap();
-                    let reader = BufReader::new(stdout);
-
-                    for line in reader.lines() {
-                        let line = line.unwrap();
-                        let maybe_progress = progress_re.captures_iter(&line).find_map(|c| {
-                            let progress = c.get(1)?.as_str();
-                            progress.parse::<f32>().ok()
-                        });
-
-                        if let Some(progress) = maybe_progress {
-                            download.progress().store(progress, Ordering::Relaxed);
-                        }
-                    }
-
-                    process.wait().is_ok()
-                });
-
-                if success {
-                    let _ = Notification::new()
-                        .summary("Ani-link")
-                        .body(&format!(
-                            "Episodio {episode} de {name} descargado correctamente"
-                        ))
-                        .show()
-                        .is_ok();
-                } else {
-                    let _ = Notification::new()
-                        .summary("Ani-link")
-                        .body(&format!(
-                            "No se ha podido descargar el episodio {episode} de {name}"
-                        ))
-                        .show()
-                        .is_ok();
-                }
-
-                download.progress().store(f32::NAN, Ordering::Relaxed);
-                *download.current() = None;
-            }
-        });
+        let download = Download::spawn(client.clone(), config);
 
         Self {
             page: Box::new(MainMenuPage {
+                catalogue_updated: catalogue_cache::updated(config2.scraper),
                 config: config2,
                 client: client2,
                 selection: main_menu_page::Selection::Search,
                 anime_list,
+                download: download.clone(),
                 waiting: false,
             }),
-            download: download2,
+            client,
+            download,
         }
     }
 }
@@ -209,25 +102,46 @@ impl App {
     }
 
     pub(crate) fn view(&self) -> iced::Element<'_, Message> {
-        let progress = self.download.progress().load(Ordering::Relaxed);
-        let maybe_current = self.download.current().clone();
+        let active = self.download.active();
+        let waiting = self.download.waiting_for_window();
 
         stack![
             self.page.view(),
-            if let Some(current) = maybe_current {
+            if active.is_empty() && waiting.is_none() {
+                row![]
+            } else {
                 row![
                     Space::new().width(Length::FillPortion(2)),
                     column![
                         Space::new().height(Length::Fill),
                         square_box(
-                            column![
-                                text(format!("{} episodio {}", current.name, current.episode)),
-                                row![
-                                    progress_bar(0.0..=100.0, progress),
-                                    Space::new().width(Length::Fixed(3.0)),
-                                    text(format!("{progress}%")).width(Length::Fixed(50.0)),
-                                ],
-                            ]
+                            Column::with_children(
+                                waiting
+                                    .map(|start| {
+                                        text(format!(
+                                            "Esperando a la ventana de descarga ({start})"
+                                        ))
+                                        .into()
+                                    })
+                                    .into_iter()
+                                    .chain(active.into_iter().map(|current| {
+                                        column![
+                                            text(format!(
+                                                "{} episodio {}",
+                                                current.token.anime.title, current.token.episode
+                                            )),
+                                            row![
+                                                progress_bar(
+                                                    0.0..=100.0,
+                                                    current.progress.percent()
+                                                ),
+                                                Space::new().width(Length::Fixed(3.0)),
+                                                text(current.progress.format()),
+                                            ],
+                                        ]
+                                        .into()
+                                    }))
+                            )
                             .padding(10)
                             .spacing(3)
                         )
@@ -244,22 +158,20 @@ impl App {
                     ]
                     .width(Length::FillPortion(1)),
                 ]
-            } else {
-                row![]
             }
         ]
         .into()
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
-        if let Message::Download(tokens) = message {
-            for token in tokens {
-                self.download
-                    .tx()
-                    .send(token)
-                    .expect("Couldn't send download token");
-            }
-
+        if let Message::Config(config) = message {
+            self.download.set_config(*config);
+            Task::none()
+        } else if let Message::Download(tokens) = message {
+            self.download.send(Command::Enqueue(tokens));
+            Task::none()
+        } else if let Message::Notification(action) = message {
+            self.run_action(action);
             Task::none()
         } else {
             let update = self.page.update(message);
@@ -279,6 +191,28 @@ impl App {
     }
 
     fn subscription(&self) -> iced::Subscription<Message> {
-        self.page.subscription()
+        Subscription::batch([self.page.subscription(), notifications::subscription()])
+    }
+
+    fn run_action(&self, action: Action) {
+        match action {
+            Action::Play(file) => episodes_page::play_episode(
+                vec![file.to_string_lossy().into_owned()],
+                &self.client,
+                &self.download,
+                self.download.config().save_on_quit,
+            ),
+            Action::Retry(ids) => {
+                notifications::clear_failed(&ids);
+                for id in ids {
+                    self.download.send(Command::Retry(id));
+                }
+            }
+            Action::OpenFolder(folder) => {
+                if let Err(err) = storage::open_folder(&folder) {
+                    eprintln!("Couldn't open {}: {err}", folder.display());
+                }
+            }
+        }
     }
 }
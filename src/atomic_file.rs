@@ -0,0 +1,50 @@
+use std::{
+    error::Error,
+    ffi::OsString,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    let temporary = temporary_path(path);
+
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    serde_json::to_writer(&mut writer, value)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+
+    fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary = OsString::from(path.as_os_str());
+    temporary.push(".tmp");
+    PathBuf::from(temporary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_file_through_a_temporary_one() {
+        let dir = std::env::temp_dir().join(format!("ani-link-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("downloads.json");
+
+        fs::write(&path, "[1,2,3]").unwrap();
+        write_json(&path, &[4, 5]).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[4,5]");
+        assert!(!temporary_path(&path).exists());
+        assert_eq!(temporary_path(&path), dir.join("downloads.json.tmp"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
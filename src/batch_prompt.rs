@@ -0,0 +1,224 @@
+use std::str::FromStr;
+
+use iced::{
+    Border, Element, Length,
+    alignment::Horizontal,
+    never,
+    theme::Palette,
+    widget::{Id, Space, column, container, rich_text, row, span, text, text_input},
+};
+
+use crate::{
+    app,
+    download::format_bytes,
+    episodes_page,
+    presets::{highlight, square_box},
+};
+
+pub const RANGE_INPUT_ID: &str = "range_input";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpisodeRange(Vec<(f64, f64)>);
+
+impl EpisodeRange {
+    pub fn contains(&self, number: f64) -> bool {
+        self.0
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&number))
+    }
+}
+
+impl FromStr for EpisodeRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |number: &str, default: f64| {
+            let number = number.trim();
+            if number.is_empty() {
+                return Some(default);
+            }
+            number
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite() && *number >= 0.0)
+        };
+
+        let ranges = s
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let invalid = || format!("rango no válido: {part}");
+                let (start, end) = if let Some((start, end)) = part.split_once('-') {
+                    (
+                        parse(start, 0.0).ok_or_else(invalid)?,
+                        parse(end, f64::INFINITY).ok_or_else(invalid)?,
+                    )
+                } else {
+                    let number = parse(part, f64::NAN)
+                        .filter(|number| !number.is_nan())
+                        .ok_or_else(invalid)?;
+                    (number, number)
+                };
+
+                if start > end {
+                    return Err(invalid());
+                }
+
+                Ok((start, end))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if ranges.is_empty() {
+            return Err("no se ha indicado ningún episodio".to_owned());
+        }
+
+        Ok(Self(ranges))
+    }
+}
+
+pub enum BatchPrompt {
+    Range {
+        input: String,
+        error: Option<String>,
+    },
+    Confirm {
+        indices: Vec<usize>,
+        skipped: usize,
+        size: u64,
+    },
+}
+
+impl BatchPrompt {
+    pub const fn range() -> Self {
+        Self::Range {
+            input: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn view(&self, palette: Palette) -> Element<'_, app::Message> {
+        let content: Element<'_, app::Message> = match self {
+            Self::Range { input, error } => column![
+                text("Descargar episodios")
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center),
+                text_input("5-12,14,20-", input)
+                    .id(Id::new(RANGE_INPUT_ID))
+                    .style(|theme: &iced::Theme, _| text_input::Style {
+                        background: iced::Background::Color(theme.palette().background),
+                        border: Border {
+                            color: theme.palette().primary,
+                            width: 1.0,
+                            ..Default::default()
+                        },
+                        icon: theme.palette().primary,
+                        placeholder: highlight(theme.palette().text, 20.0),
+                        value: theme.palette().text,
+                        selection: theme.palette().primary,
+                    })
+                    .on_input(
+                        |input| app::Message::Episodes(episodes_page::Message::RangeInput(input))
+                    )
+                    .on_submit(app::Message::Episodes(episodes_page::Message::RangeSubmit)),
+                text(error.clone().unwrap_or_default()).color(palette.danger),
+                container(
+                    rich_text![
+                        span("Confirmar:").color(palette.text),
+                        span(" Enter ").color(palette.primary),
+                        span(" Cancelar:").color(palette.text),
+                        span(" Esc").color(palette.primary),
+                    ]
+                    .on_link_click(never)
+                )
+                .align_x(Horizontal::Center)
+                .width(Length::Fill),
+            ]
+            .padding(10)
+            .spacing(3)
+            .into(),
+            Self::Confirm {
+                indices,
+                skipped,
+                size,
+            } => column![
+                text(format!(
+                    "¿Añadir {} episodios a la cola de descargas? (unos {})",
+                    indices.len(),
+                    format_bytes(*size)
+                ))
+                .width(Length::Fill)
+                .align_x(Horizontal::Center),
+                text(if *skipped > 0 {
+                    format!("Se omitirán {skipped} episodios ya descargados")
+                } else {
+                    String::new()
+                })
+                .width(Length::Fill)
+                .align_x(Horizontal::Center),
+                container(
+                    rich_text![
+                        span("Confirmar:").color(palette.text),
+                        span(" Enter Y ").color(palette.primary),
+                        span(" Cancelar:").color(palette.text),
+                        span(" Esc N").color(palette.primary),
+                    ]
+                    .on_link_click(never)
+                )
+                .align_x(Horizontal::Center)
+                .width(Length::Fill),
+            ]
+            .padding(10)
+            .spacing(3)
+            .into(),
+        };
+
+        row![
+            Space::new().width(Length::FillPortion(1)),
+            column![
+                Space::new().height(Length::FillPortion(1)),
+                square_box(content)
+                    .style(move |theme| {
+                        let mut background = theme.palette().background;
+                        background.a = 1.0;
+                        container::Style {
+                            background: Some(iced::Background::Color(background)),
+                            ..Default::default()
+                        }
+                    })
+                    .width(Length::Fill)
+                    .height(Length::Shrink),
+                Space::new().height(Length::FillPortion(1)),
+            ]
+            .width(Length::FillPortion(2)),
+            Space::new().width(Length::FillPortion(1)),
+        ]
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges_and_single_episodes() {
+        let range: EpisodeRange = "5-12, 14,20-".parse().unwrap();
+
+        assert!(range.contains(5.0));
+        assert!(range.contains(12.0));
+        assert!(range.contains(14.0));
+        assert!(range.contains(200.0));
+        assert!(!range.contains(13.0));
+        assert!(!range.contains(4.0));
+        assert!("-3".parse::<EpisodeRange>().unwrap().contains(1.0));
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        assert!("".parse::<EpisodeRange>().is_err());
+        assert!("5-a".parse::<EpisodeRange>().is_err());
+        assert!("12-5".parse::<EpisodeRange>().is_err());
+        assert!("uno".parse::<EpisodeRange>().is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, File},
+    io::BufReader,
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use dirs::cache_dir;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atomic_file,
+    scraper::{ScraperImpl, anime::Anime},
+};
+
+#[derive(Deserialize)]
+struct CatalogueCache {
+    updated: u64,
+    animes: Vec<Anime>,
+}
+
+#[derive(Deserialize)]
+struct CatalogueCacheHeader {
+    updated: u64,
+}
+
+#[derive(Serialize)]
+struct CatalogueCacheRef<'a> {
+    updated: u64,
+    animes: &'a [Anime],
+}
+
+static UPDATED: LazyLock<Mutex<HashMap<&'static str, SystemTime>>> = LazyLock::new(Mutex::default);
+
+fn path(scraper: ScraperImpl) -> Option<PathBuf> {
+    let mut path = cache_dir()?;
+    path.push("ani-link");
+    path.push(format!("{}.json", scraper.name()));
+    Some(path)
+}
+
+pub fn load(scraper: ScraperImpl) -> Option<(Vec<Anime>, SystemTime)> {
+    let file = File::open(path(scraper)?).ok()?;
+    let cache: CatalogueCache = serde_json::from_reader(BufReader::new(file)).ok()?;
+
+    if cache.animes.is_empty() {
+        return None;
+    }
+
+    let updated = remember(scraper, cache.updated);
+    Some((cache.animes, updated))
+}
+
+pub fn save(scraper: ScraperImpl, animes: &[Anime]) -> Result<(), Box<dyn Error>> {
+    let path = path(scraper).ok_or("Cache path not found")?;
+    fs::create_dir_all(path.parent().ok_or("Cache path has no parent")?)?;
+
+    let updated = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    atomic_file::write_json(&path, &CatalogueCacheRef { updated, animes })?;
+    remember(scraper, updated);
+
+    Ok(())
+}
+
+pub fn updated(scraper: ScraperImpl) -> Option<SystemTime> {
+    if let Some(updated) = UPDATED
+        .lock()
+        .expect("Couldn't lock mutex")
+        .get(scraper.name())
+    {
+        return Some(*updated);
+    }
+
+    let file = File::open(path(scraper)?).ok()?;
+    let cache: CatalogueCacheHeader = serde_json::from_reader(BufReader::new(file)).ok()?;
+
+    Some(remember(scraper, cache.updated))
+}
+
+fn remember(scraper: ScraperImpl, updated: u64) -> SystemTime {
+    let updated = UNIX_EPOCH + Duration::from_secs(updated);
+    UPDATED
+        .lock()
+        .expect("Couldn't lock mutex")
+        .insert(scraper.name(), updated);
+    updated
+}
+
+pub fn format_age(updated: SystemTime) -> String {
+    let age = SystemTime::now()
+        .duration_since(updated)
+        .unwrap_or_default()
+        .as_secs();
+
+    match age {
+        0..60 => "hace un momento".into(),
+        60..3600 => format!("hace {} min", age / 60),
+        3600..86400 => format!("hace {} h", age / 3600),
+        _ => format!("hace {} días", age / 86400),
+    }
+}
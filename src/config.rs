@@ -1,8 +1,11 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use dirs::config_dir;
+use dirs::{config_dir, home_dir, video_dir};
 use figment::{
     Figment,
     providers::{Format, Serialized, Toml},
@@ -10,16 +13,41 @@ use figment::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    scraper::ScraperImpl,
+    download::{DEFAULT_TEMPLATE, DownloadBackend},
+    http_client::HttpConfig,
+    postprocess::{self, PostProfile},
+    schedule::DownloadWindow,
+    scraper::{
+        ScraperImpl,
+        mirror::{Audio, DEFAULT_HOSTS, MirrorPreference},
+    },
     themes::{PaletteWrapper, Themes},
 };
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Config {
     pub scraper: ScraperImpl,
     pub save_on_quit: bool,
     pub theme: Themes,
     pub palette: PaletteWrapper,
+    pub http: HttpConfig,
+    pub catalogue_ttl_hours: u64,
+    pub audio: Audio,
+    pub hosts: Vec<String>,
+    pub pinned_hosts: HashMap<String, String>,
+    pub download_backend: DownloadBackend,
+    pub max_downloads: usize,
+    pub max_downloads_per_host: usize,
+    pub download_dir: PathBuf,
+    pub filename_template: String,
+    pub media_server_layout: bool,
+    pub rate_limit_kib: u64,
+    pub download_windows: Vec<DownloadWindow>,
+    pub storage_quota_gib: u64,
+    pub anime_quota_gib: u64,
+    pub cleanup_watched_days: u64,
+    pub post_profiles: Vec<PostProfile>,
+    pub anime_post_profiles: HashMap<String, String>,
 }
 
 impl Default for Config {
@@ -29,6 +57,24 @@ impl Default for Config {
             save_on_quit: true,
             theme: Themes::default(),
             palette: PaletteWrapper::default(),
+            http: HttpConfig::default(),
+            catalogue_ttl_hours: 24,
+            audio: Audio::default(),
+            hosts: DEFAULT_HOSTS.map(ToOwned::to_owned).to_vec(),
+            pinned_hosts: HashMap::new(),
+            download_backend: DownloadBackend::default(),
+            max_downloads: 2,
+            max_downloads_per_host: 1,
+            download_dir: default_download_dir(),
+            filename_template: DEFAULT_TEMPLATE.to_owned(),
+            media_server_layout: false,
+            rate_limit_kib: 0,
+            download_windows: vec![],
+            storage_quota_gib: 0,
+            anime_quota_gib: 0,
+            cleanup_watched_days: 0,
+            post_profiles: postprocess::default_profiles(),
+            anime_post_profiles: HashMap::new(),
         }
     }
 }
@@ -38,10 +84,14 @@ impl Config {
         let mut config_path = config_dir().expect("Config path not found");
         config_path.push("ani-link.toml");
 
-        let config: Self = Figment::from(Serialized::defaults(Self::default()))
+        let mut config: Self = Figment::from(Serialized::defaults(Self::default()))
             .merge(Toml::file(config_path.clone()))
             .extract()?;
 
+        if !config.download_dir.is_absolute() {
+            config.download_dir = default_download_dir();
+        }
+
         config.save()?;
 
         Ok(config)
@@ -59,6 +109,18 @@ impl Config {
         Ok(())
     }
 
+    pub const fn catalogue_ttl(&self) -> Duration {
+        Duration::from_secs(self.catalogue_ttl_hours.saturating_mul(3600))
+    }
+
+    pub fn mirror_preference(&self, slug: &str) -> MirrorPreference {
+        MirrorPreference {
+            audio: self.audio,
+            hosts: self.hosts.clone(),
+            pinned: self.pinned_hosts.get(slug).cloned(),
+        }
+    }
+
     pub fn theme(&self) -> iced::Theme {
         if matches!(self.theme, Themes::Custom) {
             iced::Theme::custom("custom", self.palette.0)
@@ -67,3 +129,9 @@ impl Config {
         }
     }
 }
+
+fn default_download_dir() -> PathBuf {
+    let mut path = video_dir().or_else(home_dir).unwrap_or_default();
+    path.push("ani-link");
+    path
+}
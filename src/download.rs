@@ -1,37 +1,1598 @@
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Write as _},
+    fs,
+    io::{BufRead, BufReader},
+    mem,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    str::FromStr,
+    sync::{
+        Arc, LazyLock, Mutex, MutexGuard,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender, channel},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
 use atomic_float::AtomicF32;
-use std::sync::{Mutex, MutexGuard, mpsc::Sender};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use tokio::runtime::Handle;
+
+use crate::{
+    config::Config,
+    download_queue, downloader,
+    extractor::{self, Stream},
+    history::{self, HistoryEntry, Outcome},
+    http_client::HttpClient,
+    library::{self, Library},
+    media_server,
+    notifications::{self, Action},
+    postprocess, schedule,
+    scraper::{
+        anime::Anime,
+        episode::Episode,
+        mirror::{Audio, Mirror, MirrorPreference},
+    },
+    storage,
+};
+
+pub const DEFAULT_TEMPLATE: &str = "{slug}/{slug}-{episode}{dub}";
 
-#[derive(Clone, Debug)]
+const ATTEMPTS: u32 = 3;
+const BACKOFF: Duration = Duration::from_secs(5);
+const TICK: Duration = Duration::from_secs(1);
+const CLEANUP_INTERVAL: Duration = Duration::from_hours(1);
+const SPEED_SAMPLE: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DownloadToken {
-    pub name: String,
-    pub slug: String,
-    pub episode: f64,
+    pub anime: Anime,
+    pub episode: Episode,
+    #[serde(default)]
+    pub special_index: Option<u32>,
+    pub preference: MirrorPreference,
+    #[serde(default)]
+    pub post_profile: Option<String>,
+}
+
+impl DownloadToken {
+    pub fn is_same(&self, other: &Self) -> bool {
+        self.anime.slug == other.anime.slug
+            && self.episode.number.total_cmp(&other.episode.number).is_eq()
+    }
+
+    pub fn path(&self, template: &str, audio: Audio, scraper: &str) -> PathBuf {
+        let mut path = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                break;
+            };
+            path.push_str(&rest[..start]);
+
+            let placeholder = &rest[start + 1..end];
+            let (name, width) = placeholder
+                .split_once(':')
+                .map_or((placeholder, None), |(name, width)| {
+                    (name, width.parse::<usize>().ok())
+                });
+
+            match name {
+                "title" => path.push_str(&sanitize(&self.anime.title)),
+                "slug" => path.push_str(&sanitize(&self.anime.slug)),
+                "episode" => path.push_str(&pad_episode(&self.episode, width.unwrap_or(0))),
+                "audio" => path.push_str(&audio.to_string()),
+                "dub" if audio == Audio::Dub => path.push_str("-dub"),
+                "dub" => {}
+                "scraper" => path.push_str(&sanitize(scraper)),
+                _ => path.push_str(&rest[start..=end]),
+            }
+
+            rest = &rest[end + 1..];
+        }
+        path.push_str(rest);
+
+        path.split(['/', '\\'])
+            .map(str::trim)
+            .filter(|component| !component.is_empty() && *component != "." && *component != "..")
+            .collect()
+    }
+}
+
+pub fn pad_episode(episode: &Episode, width: usize) -> String {
+    let number = episode.to_string();
+    let (integer, fraction) = number
+        .split_once('.')
+        .map_or((number.as_str(), None), |(integer, fraction)| {
+            (integer, Some(fraction))
+        });
+
+    fraction.map_or_else(
+        || format!("{integer:0>width$}"),
+        |fraction| format!("{integer:0>width$}.{fraction}"),
+    )
+}
+
+pub fn sanitize(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+
+    name.trim().trim_end_matches('.').to_owned()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Serialize, Deserialize)]
+pub enum DownloadBackend {
+    #[default]
+    Native,
+    YtDlp,
+}
+
+impl fmt::Display for DownloadBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Native => write!(f, "Integrado"),
+            Self::YtDlp => write!(f, "yt-dlp"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseDownloadBackendError;
+
+impl FromStr for DownloadBackend {
+    type Err = ParseDownloadBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Integrado" => Ok(Self::Native),
+            "yt-dlp" => Ok(Self::YtDlp),
+            _ => Err(ParseDownloadBackendError),
+        }
+    }
+}
+
+impl DownloadBackend {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Native => Self::YtDlp,
+            Self::YtDlp => Self::Native,
+        }
+    }
+
+    pub const fn prev(self) -> Self {
+        self.next()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DownloadState {
+    Queued,
+    Active,
+    Paused,
+    Done,
+    Unprocessed(String),
+    Failed(String),
+}
+
+impl fmt::Display for DownloadState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Queued => write!(f, "En cola"),
+            Self::Active => write!(f, "Descargando"),
+            Self::Paused => write!(f, "En pausa"),
+            Self::Done => write!(f, "Completado"),
+            Self::Unprocessed(reason) => write!(f, "Completado sin postprocesar: {reason}"),
+            Self::Failed(reason) => write!(f, "Error: {reason}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Enqueue(Vec<DownloadToken>),
+    Pause(u64),
+    Resume(u64),
+    Cancel(u64),
+    Retry(u64),
+    MoveUp(u64),
+    MoveDown(u64),
+    SetProfile(u64, Option<String>),
+    Finished(u64, Result<Option<String>, String>),
+    Deferred(u64, Vec<String>),
+}
+
+#[derive(Debug)]
+pub struct Progress {
+    percent: AtomicF32,
+    downloaded: AtomicU64,
+    total: AtomicU64,
+    interrupted: AtomicBool,
+    cancelled: AtomicBool,
+    processing: AtomicBool,
+    speed: AtomicU64,
+    eta: AtomicU64,
+    sample: Mutex<Option<(Instant, u64)>>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self {
+            percent: AtomicF32::new(0.0),
+            downloaded: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            interrupted: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            processing: AtomicBool::new(false),
+            speed: AtomicU64::new(0),
+            eta: AtomicU64::new(u64::MAX),
+            sample: Mutex::new(None),
+        }
+    }
+}
+
+impl Progress {
+    pub fn percent(&self) -> f32 {
+        self.percent.load(Ordering::Relaxed)
+    }
+
+    pub fn set_percent(&self, percent: f32) {
+        self.percent.store(percent, Ordering::Relaxed);
+    }
+
+    pub fn bytes(&self) -> (u64, Option<u64>) {
+        let total = self.total.load(Ordering::Relaxed);
+        (
+            self.downloaded.load(Ordering::Relaxed),
+            (total > 0).then_some(total),
+        )
+    }
+
+    pub fn set_bytes(&self, downloaded: u64, total: Option<u64>) {
+        self.downloaded.store(downloaded, Ordering::Relaxed);
+        self.total
+            .store(total.unwrap_or_default(), Ordering::Relaxed);
+        self.update_percent();
+        self.update_speed();
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.downloaded.fetch_add(bytes, Ordering::Relaxed);
+        self.update_percent();
+        self.update_speed();
+    }
+
+    pub fn speed(&self) -> Option<u64> {
+        Some(self.speed.load(Ordering::Relaxed)).filter(|speed| *speed > 0)
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        Some(self.eta.load(Ordering::Relaxed))
+            .filter(|eta| *eta != u64::MAX)
+            .map(Duration::from_secs)
+    }
+
+    fn set_speed(&self, speed: Option<u64>, eta: Option<u64>) {
+        self.speed
+            .store(speed.unwrap_or_default(), Ordering::Relaxed);
+        self.eta.store(eta.unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    fn set_telemetry(&self, telemetry: &Telemetry) {
+        if let Some(total) = telemetry.total {
+            #[allow(
+                clippy::cast_precision_loss,
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss
+            )]
+            let downloaded = (f64::from(telemetry.percent) / 100.0 * total as f64) as u64;
+            self.downloaded.store(downloaded, Ordering::Relaxed);
+            self.total.store(total, Ordering::Relaxed);
+        }
+        self.set_percent(telemetry.percent);
+        self.set_speed(telemetry.speed, telemetry.eta);
+    }
+
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.interrupt();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn start_processing(&self) {
+        self.set_bytes(0, None);
+        self.set_percent(0.0);
+        self.set_speed(None, None);
+        self.processing.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_processing(&self) -> bool {
+        self.processing.load(Ordering::Relaxed)
+    }
+
+    pub fn format(&self) -> String {
+        if self.is_processing() {
+            return format!("Procesando {:.1}%", self.percent());
+        }
+
+        let mut formatted = match self.bytes() {
+            (0, _) => format!("{:.1}%", self.percent()),
+            (downloaded, Some(total)) => format!(
+                "{:.1}% ({} / {})",
+                self.percent(),
+                format_bytes(downloaded),
+                format_bytes(total)
+            ),
+            (downloaded, None) => format_bytes(downloaded),
+        };
+
+        if let Some(speed) = self.speed() {
+            let _ = write!(formatted, " - {}/s", format_bytes(speed));
+        }
+        if let Some(eta) = self.eta() {
+            let _ = write!(formatted, " - {}", format_duration(eta));
+        }
+
+        formatted
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn update_percent(&self) {
+        if let (downloaded, Some(total)) = self.bytes() {
+            let percent = downloaded as f64 / total as f64 * 100.0;
+            #[allow(clippy::cast_possible_truncation)]
+            self.set_percent(percent.clamp(0.0, 100.0) as f32);
+        }
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn update_speed(&self) {
+        let (downloaded, total) = self.bytes();
+        let now = Instant::now();
+        let mut sample = self.sample.lock().expect("Couldn't lock mutex");
+
+        let Some((start, bytes)) = *sample else {
+            *sample = Some((now, downloaded));
+            return;
+        };
+
+        let elapsed = now.duration_since(start);
+        if downloaded < bytes {
+            *sample = Some((now, downloaded));
+            return;
+        }
+        if elapsed < SPEED_SAMPLE {
+            return;
+        }
+        *sample = Some((now, downloaded));
+        drop(sample);
+
+        let speed = ((downloaded - bytes) as f64 / elapsed.as_secs_f64()) as u64;
+        self.set_speed(
+            Some(speed),
+            total
+                .filter(|_| speed > 0)
+                .map(|total| total.saturating_sub(downloaded) / speed),
+        );
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Telemetry {
+    percent: f32,
+    total: Option<u64>,
+    speed: Option<u64>,
+    eta: Option<u64>,
+}
+
+static YT_DLP_PROGRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\[download\]\s+([0-9.]+)%(?:\s+of\s+~?\s*([0-9.]+)\s*([KMGTP]?i?B))?(?:\s+at\s+([0-9.]+)\s*([KMGTP]?i?B)/s)?(?:\s+ETA\s+([0-9:]+))?",
+    )
+    .unwrap()
+});
+
+fn parse_yt_dlp_progress(line: &str) -> Option<Telemetry> {
+    let captures = YT_DLP_PROGRESS.captures(line)?;
+    let size = |number: usize, unit: usize| {
+        parse_size(captures.get(number)?.as_str(), captures.get(unit)?.as_str())
+    };
+
+    Some(Telemetry {
+        percent: captures.get(1)?.as_str().parse().ok()?,
+        total: size(2, 3),
+        speed: size(4, 5),
+        eta: captures.get(6).and_then(|eta| {
+            eta.as_str()
+                .split(':')
+                .try_fold(0, |secs, part| Some(secs * 60 + part.parse::<u64>().ok()?))
+        }),
+    })
+}
+
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn parse_size(number: &str, unit: &str) -> Option<u64> {
+    let number = number.parse::<f64>().ok()?;
+    let base: f64 = if unit.contains('i') { 1024.0 } else { 1000.0 };
+    let exponent = "KMGTP"
+        .find(unit.chars().next()?)
+        .map_or(0, |index| index + 1);
+
+    Some((number * base.powi(i32::try_from(exponent).ok()?)) as u64)
+}
+
+#[derive(Default)]
+struct HostSlots(Mutex<HashMap<String, usize>>);
+
+struct HostSlot<'a> {
+    slots: &'a HostSlots,
+    host: String,
+}
+
+impl HostSlots {
+    fn acquire(&self, host: &str, limit: usize) -> Option<HostSlot<'_>> {
+        let mut slots = self.0.lock().expect("Couldn't lock mutex");
+        let used = slots.entry(host.to_owned()).or_default();
+
+        if limit > 0 && *used >= limit {
+            return None;
+        }
+
+        *used += 1;
+        drop(slots);
+
+        Some(HostSlot {
+            slots: self,
+            host: host.to_owned(),
+        })
+    }
+
+    fn is_full(&self, host: &str, limit: usize) -> bool {
+        limit > 0
+            && self
+                .0
+                .lock()
+                .expect("Couldn't lock mutex")
+                .get(host)
+                .is_some_and(|used| *used >= limit)
+    }
+}
+
+impl Drop for HostSlot<'_> {
+    fn drop(&mut self) {
+        if let Some(used) = self
+            .slots
+            .0
+            .lock()
+            .expect("Couldn't lock mutex")
+            .get_mut(&self.host)
+        {
+            *used = used.saturating_sub(1);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RateLimit {
+    bytes_per_second: AtomicU64,
+    next: Mutex<Option<Instant>>,
+    jobs: AtomicUsize,
+}
+
+impl RateLimit {
+    fn set(&self, kib_per_second: u64) {
+        self.bytes_per_second
+            .store(kib_per_second.saturating_mul(1024), Ordering::Relaxed);
+    }
+
+    fn share_kib(&self) -> u64 {
+        let jobs = self.jobs.load(Ordering::Relaxed).max(1) as u64;
+        (self.bytes_per_second.load(Ordering::Relaxed) / 1024 / jobs).max(1)
+    }
+
+    pub fn delay(&self, bytes: u64) -> Duration {
+        let limit = self.bytes_per_second.load(Ordering::Relaxed);
+        if limit == 0 {
+            return Duration::ZERO;
+        }
+
+        let now = Instant::now();
+        let mut next = self.next.lock().expect("Couldn't lock mutex");
+        #[allow(clippy::cast_precision_loss)]
+        let end = next.map_or(now, |next| next.max(now))
+            + Duration::from_secs_f64(bytes as f64 / limit as f64);
+        *next = Some(end);
+        drop(next);
+
+        end.saturating_duration_since(now)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadItem {
+    pub id: u64,
+    pub token: DownloadToken,
+    pub state: DownloadState,
+    #[serde(skip)]
+    pub progress: Arc<Progress>,
 }
 
 pub struct Download {
-    tx: Sender<DownloadToken>,
-    current: Mutex<Option<DownloadToken>>,
-    progress: AtomicF32,
+    tx: Sender<Command>,
+    config: Mutex<Config>,
+    items: Mutex<Vec<DownloadItem>>,
+    library: Mutex<Library>,
+    hosts: Arc<HostSlots>,
+    rate: Arc<RateLimit>,
+    waiting: AtomicBool,
 }
 
 impl Download {
-    pub const fn new(tx: Sender<DownloadToken>) -> Self {
-        Self {
+    pub fn spawn(client: HttpClient, config: Config) -> Arc<Self> {
+        let (tx, rx) = channel();
+
+        let items = download_queue::load();
+        let pending = items
+            .iter()
+            .filter(|item| item.state == DownloadState::Queued)
+            .count();
+
+        if pending > 0 {
+            notifications::notify(
+                notifications::DOWNLOADS,
+                &format!("Reanudando {pending} descargas pendientes..."),
+                vec![],
+            );
+        }
+
+        let rate = Arc::<RateLimit>::default();
+        rate.set(config.rate_limit_kib);
+
+        let download = Arc::new(Self {
             tx,
-            current: Mutex::new(None),
-            progress: AtomicF32::new(f32::NAN),
+            config: Mutex::new(config),
+            items: Mutex::new(items),
+            library: Mutex::new(Library::load()),
+            hosts: Arc::default(),
+            rate,
+            waiting: AtomicBool::new(false),
+        });
+
+        let worker = download.clone();
+        let handle = Handle::current();
+        thread::spawn(move || worker.run(&rx, &client, &handle));
+
+        download
+    }
+
+    pub fn send(&self, command: Command) {
+        self.tx
+            .send(command)
+            .expect("Couldn't send download command");
+    }
+
+    pub fn config(&self) -> Config {
+        self.config.lock().expect("Couldn't lock mutex").clone()
+    }
+
+    pub fn set_config(&self, config: Config) {
+        self.rate.set(config.rate_limit_kib);
+        *self.config.lock().expect("Couldn't lock mutex") = config;
+    }
+
+    pub fn items(&self) -> MutexGuard<'_, Vec<DownloadItem>> {
+        self.items.lock().expect("Couldn't lock mutex")
+    }
+
+    pub fn library(&self) -> MutexGuard<'_, Library> {
+        self.library.lock().expect("Couldn't lock mutex")
+    }
+
+    pub fn pending(&self) -> usize {
+        self.items()
+            .iter()
+            .filter(|item| matches!(item.state, DownloadState::Queued | DownloadState::Active))
+            .count()
+    }
+
+    pub fn waiting_for_window(&self) -> Option<schedule::TimeOfDay> {
+        if !self.waiting.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        schedule::next_opening(&self.config().download_windows)
+    }
+
+    pub fn is_busy(&self, path: &Path) -> bool {
+        let config = self.config();
+        let file = path.with_extension("");
+
+        self.items()
+            .iter()
+            .filter(|item| {
+                matches!(
+                    item.state,
+                    DownloadState::Queued | DownloadState::Active | DownloadState::Paused
+                )
+            })
+            .flat_map(|item| {
+                Audio::iter()
+                    .map(|audio| stem(&config, &item.token, audio, config.media_server_layout))
+            })
+            .any(|stem| stem.starts_with(path) || stem == file)
+    }
+
+    pub fn active(&self) -> Vec<DownloadItem> {
+        self.items()
+            .iter()
+            .filter(|item| item.state == DownloadState::Active)
+            .cloned()
+            .collect()
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn run(self: Arc<Self>, rx: &Receiver<Command>, client: &HttpClient, handle: &Handle) {
+        let mut next_id = self
+            .items()
+            .iter()
+            .map(|item| item.id + 1)
+            .max()
+            .unwrap_or(0);
+        let mut running = HashSet::new();
+        let mut blocked = HashMap::new();
+        let mut cleaned: Option<Instant> = None;
+
+        loop {
+            let command = match rx.recv_timeout(TICK) {
+                Ok(command) => Some(command),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            let mut items = self.items();
+            let position = |items: &[DownloadItem], id| items.iter().position(|item| item.id == id);
+            let mut changed = command.is_some();
+
+            let open = schedule::is_open(&self.config().download_windows);
+            if !open {
+                for item in items
+                    .iter_mut()
+                    .filter(|item| item.state == DownloadState::Active)
+                {
+                    item.progress.interrupt();
+                    item.state = DownloadState::Queued;
+                    changed = true;
+                }
+            }
+
+            match command {
+                Some(Command::Enqueue(tokens)) => {
+                    for token in tokens {
+                        if let Some(item) = items.iter_mut().find(|item| item.token.is_same(&token))
+                        {
+                            if matches!(item.state, DownloadState::Failed(_)) {
+                                item.state = DownloadState::Queued;
+                            }
+                        } else {
+                            items.push(DownloadItem {
+                                id: next_id,
+                                token,
+                                state: DownloadState::Queued,
+                                progress: Arc::default(),
+                            });
+                            next_id += 1;
+                        }
+                    }
+                }
+                Some(Command::Pause(id)) => {
+                    if let Some(index) = position(&items, id) {
+                        let item = &mut items[index];
+                        if matches!(item.state, DownloadState::Queued | DownloadState::Active) {
+                            item.progress.interrupt();
+                            item.state = DownloadState::Paused;
+                        }
+                    }
+                }
+                Some(Command::Resume(id) | Command::Retry(id)) => {
+                    if let Some(index) = position(&items, id) {
+                        let item = &mut items[index];
+                        if matches!(item.state, DownloadState::Paused | DownloadState::Failed(_)) {
+                            item.state = DownloadState::Queued;
+                        }
+                    }
+                }
+                Some(Command::Cancel(id)) => {
+                    if let Some(index) = position(&items, id) {
+                        items.remove(index).progress.cancel();
+                        download_queue::remove_log(id);
+                    }
+                }
+                Some(Command::MoveUp(id)) => {
+                    if let Some(index) = position(&items, id)
+                        && index > 0
+                    {
+                        items.swap(index, index - 1);
+                    }
+                }
+                Some(Command::MoveDown(id)) => {
+                    if let Some(index) = position(&items, id)
+                        && index + 1 < items.len()
+                    {
+                        items.swap(index, index + 1);
+                    }
+                }
+                Some(Command::SetProfile(id, profile)) => {
+                    if let Some(index) = position(&items, id)
+                        && items[index].state != DownloadState::Active
+                    {
+                        items[index].token.post_profile = profile;
+                    }
+                }
+                Some(Command::Finished(id, result)) => {
+                    running.remove(&id);
+                    if let Some(index) = position(&items, id) {
+                        let item = &mut items[index];
+                        if item.state == DownloadState::Active {
+                            if result.is_ok() {
+                                self.library().scan(
+                                    &self.config(),
+                                    &item.token.anime,
+                                    std::slice::from_ref(&item.token.episode),
+                                );
+                            }
+
+                            item.state = match result {
+                                Ok(None) => DownloadState::Done,
+                                Ok(Some(reason)) => DownloadState::Unprocessed(reason),
+                                Err(reason) => DownloadState::Failed(reason),
+                            };
+                        }
+                    }
+                }
+                Some(Command::Deferred(id, hosts)) => {
+                    running.remove(&id);
+                    if let Some(index) = position(&items, id)
+                        && items[index].state == DownloadState::Active
+                    {
+                        items[index].state = DownloadState::Queued;
+                        blocked.insert(id, hosts);
+                    }
+                }
+                None => {}
+            }
+
+            if open {
+                changed |= self.start_next(&mut items, &mut running, &mut blocked, client, handle);
+            }
+
+            self.waiting.store(
+                !open && items.iter().any(|item| item.state == DownloadState::Queued),
+                Ordering::Relaxed,
+            );
+
+            if changed && let Err(err) = download_queue::save(&items) {
+                eprintln!("Couldn't save the download queue: {err}");
+            }
+            drop(items);
+
+            if cleaned.is_none_or(|cleaned| cleaned.elapsed() >= CLEANUP_INTERVAL) {
+                cleaned = Some(Instant::now());
+                self.cleanup();
+            }
+        }
+    }
+
+    fn cleanup(&self) {
+        let config = self.config();
+        if config.cleanup_watched_days == 0 {
+            return;
+        }
+
+        let expired = storage::expired(
+            self.library().watched(),
+            config.cleanup_watched_days,
+            history::now(),
+        );
+        let removed = expired
+            .into_iter()
+            .filter(|file| match storage::remove(&config.download_dir, file) {
+                Ok(()) => true,
+                Err(err) => {
+                    eprintln!("Couldn't remove {}: {err}", file.display());
+                    false
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if removed.is_empty() {
+            return;
+        }
+
+        let mut library = self.library();
+        for file in &removed {
+            library.forget(file);
+        }
+        drop(library);
+
+        notifications::notify(
+            notifications::STORAGE,
+            &format!(
+                "Se han borrado {} episodios vistos hace más de {} días",
+                removed.len(),
+                config.cleanup_watched_days
+            ),
+            vec![],
+        );
+    }
+
+    fn start_next(
+        &self,
+        items: &mut [DownloadItem],
+        running: &mut HashSet<u64>,
+        blocked: &mut HashMap<u64, Vec<String>>,
+        client: &HttpClient,
+        handle: &Handle,
+    ) -> bool {
+        let config = self.config();
+        let mut started = false;
+
+        for item in items
+            .iter_mut()
+            .filter(|item| item.state == DownloadState::Queued)
+        {
+            if running.len() >= config.max_downloads.max(1) {
+                break;
+            }
+            if running.contains(&item.id) {
+                continue;
+            }
+            if blocked.get(&item.id).is_some_and(|hosts| {
+                hosts
+                    .iter()
+                    .all(|host| self.hosts.is_full(host, config.max_downloads_per_host))
+            }) {
+                continue;
+            }
+            blocked.remove(&item.id);
+
+            item.state = DownloadState::Active;
+            item.progress = Arc::default();
+            running.insert(item.id);
+
+            let job = Job {
+                id: item.id,
+                token: item.token.clone(),
+                progress: item.progress.clone(),
+                config: config.clone(),
+                client: client.clone(),
+                handle: handle.clone(),
+                hosts: self.hosts.clone(),
+                rate: self.rate.clone(),
+                tried: Mutex::default(),
+                deferred: Mutex::default(),
+            };
+            let tx = self.tx.clone();
+            started = true;
+
+            thread::spawn(move || {
+                let result = job.run();
+                let deferred = mem::take(&mut *job.deferred.lock().expect("Couldn't lock mutex"));
+                let _ = tx.send(if deferred.is_empty() {
+                    Command::Finished(job.id, result)
+                } else {
+                    Command::Deferred(job.id, deferred)
+                });
+            });
+        }
+
+        started
+    }
+}
+
+struct Job {
+    id: u64,
+    token: DownloadToken,
+    progress: Arc<Progress>,
+    config: Config,
+    client: HttpClient,
+    handle: Handle,
+    hosts: Arc<HostSlots>,
+    rate: Arc<RateLimit>,
+    tried: Mutex<Vec<String>>,
+    deferred: Mutex<Vec<String>>,
+}
+
+impl Job {
+    fn run(&self) -> Result<Option<String>, String> {
+        let started = Instant::now();
+        self.rate.jobs.fetch_add(1, Ordering::Relaxed);
+        let result = self.fetch();
+        self.rate.jobs.fetch_sub(1, Ordering::Relaxed);
+
+        if self.progress.is_cancelled() {
+            for stem in self.stems() {
+                remove_partial_files(&stem);
+            }
+            download_queue::remove_log(self.id);
+        } else if let Err(reason) = &result
+            && !self.progress.is_interrupted()
+            && !self.is_deferred()
+        {
+            for stem in self.stems() {
+                remove_partial_files(&stem);
+            }
+
+            notifications::failed(
+                &self.token.anime.slug,
+                self.id,
+                &format!(
+                    "No se ha podido descargar el episodio {} de {}: {reason}",
+                    self.token.episode, self.token.anime.title
+                ),
+            );
+        }
+
+        let outcome = match &result {
+            _ if self.progress.is_cancelled() => Some(Outcome::Cancelled),
+            Ok(None) => Some(Outcome::Done),
+            Ok(Some(reason)) => Some(Outcome::Unprocessed(reason.clone())),
+            Err(_) if self.progress.is_interrupted() || self.is_deferred() => None,
+            Err(reason) => Some(Outcome::Failed(reason.clone())),
+        };
+        if let Some(outcome) = outcome {
+            self.record(started, outcome);
+        }
+
+        result
+    }
+
+    fn record(&self, started: Instant, outcome: Outcome) {
+        let size = self
+            .stems()
+            .filter(|_| matches!(outcome, Outcome::Done | Outcome::Unprocessed(_)))
+            .find_map(|stem| library::find_video(&stem))
+            .and_then(|file| fs::metadata(file).ok())
+            .map_or_else(|| self.progress.bytes().0, |metadata| metadata.len());
+
+        let entry = HistoryEntry {
+            finished: history::now(),
+            anime: self.token.anime.title.clone(),
+            episode: self.token.episode.number,
+            duration: started.elapsed().as_secs(),
+            size,
+            host: Some(self.tried.lock().expect("Couldn't lock mutex").join(", "))
+                .filter(|hosts| !hosts.is_empty()),
+            outcome,
+        };
+
+        if let Err(err) = history::record(&entry) {
+            eprintln!("Couldn't write the download history: {err}");
+        }
+    }
+
+    fn fetch(&self) -> Result<Option<String>, String> {
+        let DownloadToken {
+            anime,
+            episode,
+            preference,
+            ..
+        } = &self.token;
+
+        if let Err(reason) = storage::preflight(&self.config, &self.stem(preference.audio)) {
+            self.log(&reason);
+            return Err(reason);
+        }
+
+        let mirrors = self.handle.block_on(async {
+            let mut retries = 3;
+            loop {
+                match self
+                    .config
+                    .scraper
+                    .try_get_mirrors(&self.client, &anime.slug, episode.number)
+                    .await
+                {
+                    Err(err) if err.is_retryable() && retries > 0 => {
+                        retries -= 1;
+                        tokio::time::sleep(Duration::from_secs(2)).await;
+                    }
+                    result => break result,
+                }
+            }
+        });
+
+        let mirrors = match mirrors {
+            Ok(mirrors) => preference.rank(mirrors),
+            Err(err) => {
+                self.log(&format!("No se han podido obtener los mirrors: {err}"));
+                return Err(err.to_string());
+            }
+        };
+
+        if mirrors.is_empty() {
+            return Err("no hay mirrors disponibles".into());
+        }
+
+        let mut result = Err(String::new());
+        for attempt in 0..ATTEMPTS {
+            if attempt > 0 && !self.wait(BACKOFF * 2_u32.pow(attempt - 1)) {
+                break;
+            }
+
+            result = self.try_mirrors(&mirrors);
+            if result.is_ok() || self.progress.is_interrupted() || self.is_deferred() {
+                break;
+            }
+        }
+
+        if self.progress.is_interrupted() {
+            return Err("interrumpida".into());
+        }
+
+        let audio = result?;
+        let processed = self.post_process(audio);
+
+        if self.config.media_server_layout
+            && let Err(err) = self.handle.block_on(media_server::write_metadata(
+                &self.client,
+                &self.config.download_dir,
+                &self.token,
+                &self.stem(audio),
+            ))
+        {
+            self.log(&format!("No se han podido guardar los metadatos: {err}"));
+        }
+
+        let video = library::find_video(&self.stem(audio));
+        if let Some(file) = &video
+            && let Ok(metadata) = fs::metadata(file)
+        {
+            storage::add_usage(file, metadata.len());
+        }
+
+        let actions = video
+            .map(|file| {
+                let folder = file.parent().map(Path::to_path_buf);
+                [Some(Action::Play(file)), folder.map(Action::OpenFolder)]
+            })
+            .into_iter()
+            .flatten()
+            .flatten()
+            .collect();
+
+        let body = match &processed {
+            Ok(()) => format!("Episodio {episode} de {anime} descargado correctamente"),
+            Err(reason) => format!(
+                "Episodio {episode} de {anime} descargado, pero no se ha podido procesar: {reason}"
+            ),
+        };
+        notifications::notify(&anime.slug, &body, actions);
+
+        Ok(processed.err())
+    }
+
+    fn post_process(&self, audio: Audio) -> Result<(), String> {
+        let Some(name) = self
+            .token
+            .post_profile
+            .as_ref()
+            .or_else(|| self.config.anime_post_profiles.get(&self.token.anime.slug))
+        else {
+            return Ok(());
+        };
+
+        let result = self
+            .config
+            .post_profiles
+            .iter()
+            .find(|profile| &profile.name == name)
+            .ok_or_else(|| format!("no existe el perfil {name}"))
+            .and_then(|profile| {
+                let input = library::find_video(&self.stem(audio))
+                    .ok_or("no se ha encontrado el archivo descargado")?;
+
+                self.progress.start_processing();
+                postprocess::run(profile, &input, &self.progress)
+                    .map(|_| ())
+                    .map_err(|reason| format!("perfil {name}: {reason}"))
+            });
+
+        if let Err(reason) = &result {
+            self.log(&format!("No se ha podido postprocesar: {reason}"));
+        }
+
+        result
+    }
+
+    fn try_mirrors(&self, mirrors: &[Mirror]) -> Result<Audio, String> {
+        let mut reason = String::new();
+        let mut deferred = vec![];
+
+        for mirror in mirrors {
+            if self.progress.is_interrupted() {
+                return Err(reason);
+            }
+
+            let host = mirror.host().unwrap_or_default();
+            let Some(_slot) = self
+                .hosts
+                .acquire(&host, self.config.max_downloads_per_host)
+            else {
+                deferred.push(host);
+                continue;
+            };
+
+            let mut tried = self.tried.lock().expect("Couldn't lock mutex");
+            if !host.is_empty() && !tried.contains(&host) {
+                tried.push(host);
+            }
+            drop(tried);
+
+            match self.download_mirror(mirror) {
+                Ok(()) => return Ok(mirror.audio),
+                Err(err) => {
+                    self.log(&format!("{}: {err}", mirror.url));
+                    reason = err;
+                }
+            }
+        }
+
+        *self.deferred.lock().expect("Couldn't lock mutex") = deferred;
+
+        Err(reason)
+    }
+
+    fn is_deferred(&self) -> bool {
+        !self
+            .deferred
+            .lock()
+            .expect("Couldn't lock mutex")
+            .is_empty()
+    }
+
+    fn wait(&self, duration: Duration) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < duration {
+            if self.progress.is_interrupted() {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        true
+    }
+
+    fn stem(&self, audio: Audio) -> PathBuf {
+        stem(
+            &self.config,
+            &self.token,
+            audio,
+            self.config.media_server_layout,
+        )
+    }
+
+    fn stems(&self) -> impl Iterator<Item = PathBuf> {
+        Audio::iter().map(|audio| self.stem(audio))
+    }
+
+    fn download_mirror(&self, mirror: &Mirror) -> Result<(), String> {
+        let stem = self.stem(mirror.audio);
+        claim_partial_files(&stem, &mirror.url);
+
+        let result = self.download_stream(mirror, &stem);
+        if result.is_ok() {
+            let _ = fs::remove_file(mirror_marker(&stem));
+        }
+
+        result
+    }
+
+    fn download_stream(&self, mirror: &Mirror, stem: &Path) -> Result<(), String> {
+        let stream = self
+            .handle
+            .block_on(extractor::resolve(&self.client, &mirror.url));
+
+        if self.config.download_backend == DownloadBackend::Native
+            && let Some(stream) = &stream
+        {
+            return self
+                .handle
+                .block_on(downloader::download(
+                    &self.client,
+                    stream,
+                    stem,
+                    &self.progress,
+                    &self.rate,
+                ))
+                .map(|_| ())
+                .map_err(|err| err.to_string());
+        }
+
+        self.yt_dlp(&mirror.url, stream.as_ref(), stem)
+    }
+
+    fn yt_dlp(&self, url: &str, stream: Option<&Stream>, stem: &Path) -> Result<(), String> {
+        let mut command = process::Command::new(format!(
+            "yt-dlp{}",
+            if cfg!(target_os = "windows") {
+                ".exe"
+            } else {
+                ""
+            }
+        ));
+
+        #[cfg(target_os = "windows")]
+        command.creation_flags(0x08000000);
+
+        if self.config.rate_limit_kib > 0 {
+            command
+                .arg("--limit-rate")
+                .arg(format!("{}K", self.rate.share_kib()));
+        }
+
+        match stream {
+            Some(stream) => {
+                for (name, value) in &stream.headers {
+                    command.arg("--add-header").arg(format!("{name}: {value}"));
+                }
+                command.arg(&stream.url)
+            }
+            None => command.arg(url),
+        };
+
+        let mut child = command
+            .arg("--no-check-certificates")
+            .arg("--newline")
+            .arg("--output")
+            .arg(format!("{}.%(ext)s", stem.display()))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("no se ha podido ejecutar yt-dlp: {err}"))?;
+
+        let stderr = child.stderr.take().unwrap();
+        let stderr = thread::spawn(move || {
+            BufReader::new(stderr)
+                .lines()
+                .map_while(Result::ok)
+                .collect::<Vec<_>>()
+        });
+
+        let stdout = child.stdout.take().unwrap();
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if self.progress.is_interrupted() {
+                let _ = child.kill();
+                break;
+            }
+
+            if let Some(telemetry) = parse_yt_dlp_progress(&line) {
+                self.progress.set_telemetry(&telemetry);
+            }
         }
+
+        let status = child
+            .wait()
+            .map_err(|err| format!("yt-dlp no ha terminado correctamente: {err}"))?;
+        let stderr = stderr.join().unwrap_or_default();
+
+        if !stderr.is_empty() {
+            self.log(&stderr.join("\n"));
+        }
+
+        if status.success() {
+            return Ok(());
+        }
+
+        Err(stderr
+            .iter()
+            .rev()
+            .find_map(|line| line.strip_prefix("ERROR:"))
+            .map_or_else(
+                || {
+                    status.code().map_or_else(
+                        || "yt-dlp ha sido interrumpido".to_owned(),
+                        |code| format!("yt-dlp ha terminado con el código {code}"),
+                    )
+                },
+                |line| line.trim().to_owned(),
+            ))
     }
 
-    pub fn tx(&self) -> Sender<DownloadToken> {
-        self.tx.clone()
+    fn log(&self, message: &str) {
+        if let Err(err) = download_queue::log(self.id, message) {
+            eprintln!("Couldn't write the download log: {err}");
+        }
+    }
+}
+
+pub fn stem(
+    config: &Config,
+    token: &DownloadToken,
+    audio: Audio,
+    media_server_layout: bool,
+) -> PathBuf {
+    config.download_dir.join(if media_server_layout {
+        media_server::episode_path(token, audio)
+    } else {
+        token.path(&config.filename_template, audio, config.scraper.name())
+    })
+}
+
+pub fn stem_extension<'a>(stem: &Path, file_name: &'a str) -> Option<&'a str> {
+    let name = stem.file_name()?.to_string_lossy();
+    let extension = file_name.strip_prefix(name.as_ref())?.strip_prefix('.')?;
+
+    let first = extension.split('.').next()?;
+    (!first.is_empty() && !first.chars().all(|c| c.is_ascii_digit())).then_some(extension)
+}
+
+fn mirror_marker(stem: &Path) -> PathBuf {
+    let mut marker = stem.as_os_str().to_owned();
+    marker.push(".mirror.part");
+    PathBuf::from(marker)
+}
+
+fn claim_partial_files(stem: &Path, url: &str) {
+    let marker = mirror_marker(stem);
+    if fs::read_to_string(&marker).is_ok_and(|owner| owner == url) {
+        return;
     }
 
-    pub fn current(&self) -> MutexGuard<'_, Option<DownloadToken>> {
-        self.current.lock().expect("Couldn't lock mutex")
+    remove_partial_files(stem);
+    if let Some(parent) = stem.parent()
+        && fs::create_dir_all(parent).is_ok()
+    {
+        let _ = fs::write(marker, url);
     }
+}
+
+fn remove_partial_files(stem: &Path) {
+    let Some(parent) = stem.parent() else {
+        return;
+    };
+
+    let Ok(entries) = fs::read_dir(parent) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+
+        if stem_extension(stem, &file_name.to_string_lossy())
+            .is_some_and(|extension| extension.contains("part") || extension.ends_with("ytdl"))
+        {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+pub mod fixtures {
+    use super::DownloadToken;
+    use crate::scraper::{anime::Anime, episode::Episode, mirror::MirrorPreference};
+
+    pub fn token(title: &str, episode: Episode) -> DownloadToken {
+        DownloadToken {
+            anime: Anime {
+                title: title.to_owned(),
+                alt_titles: vec![],
+                slug: title.to_lowercase().replace(' ', "-"),
+                id: "1".to_owned(),
+                synopsis: String::new(),
+                image_url: String::new(),
+                genres: vec![],
+                year: None,
+                kind: None,
+                status: None,
+                episode_count: None,
+            },
+            episode,
+            special_index: None,
+            preference: MirrorPreference::default(),
+            post_profile: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fixtures::token, *};
+
+    #[test]
+    fn expands_the_default_template() {
+        let token = token("Shingeki no Kyojin", Episode::new(3.0));
+
+        assert_eq!(
+            token.path(
+                "{slug}/{slug}-{episode}{dub}",
+                Audio::Sub,
+                "AnimeAv1Scraper"
+            ),
+            PathBuf::from("shingeki-no-kyojin/shingeki-no-kyojin-3")
+        );
+        assert_eq!(
+            token.path(
+                "{slug}/{slug}-{episode}{dub}",
+                Audio::Dub,
+                "AnimeAv1Scraper"
+            ),
+            PathBuf::from("shingeki-no-kyojin/shingeki-no-kyojin-3-dub")
+        );
+    }
+
+    #[test]
+    fn pads_episode_numbers() {
+        assert_eq!(
+            token("Title", Episode::new(7.0)).path("{episode:3}", Audio::Sub, ""),
+            PathBuf::from("007")
+        );
+        assert_eq!(
+            token("Title", Episode::new(7.5)).path("{episode:2}", Audio::Sub, ""),
+            PathBuf::from("07.5")
+        );
+        assert_eq!(
+            token("Title", Episode::new(120.0)).path("{episode:2}", Audio::Sub, ""),
+            PathBuf::from("120")
+        );
+    }
+
+    #[test]
+    fn matches_files_of_the_same_episode_only() {
+        let stem = Path::new("/videos/show/show-1");
+
+        assert_eq!(stem_extension(stem, "show-1.mp4"), Some("mp4"));
+        assert_eq!(stem_extension(stem, "show-1.mp4.part"), Some("mp4.part"));
+        assert_eq!(stem_extension(stem, "show-1.5.mp4"), None);
+        assert_eq!(stem_extension(stem, "show-10.mp4"), None);
+        assert_eq!(stem_extension(stem, "show-1-dub.mp4"), None);
+    }
+
+    #[test]
+    fn sanitizes_titles_and_drops_unsafe_components() {
+        let token = token("Re:Zero / Kara Hajimeru?", Episode::new(1.0));
+
+        assert_eq!(
+            token.path(
+                "{scraper}/../{title} - {audio} {unknown}",
+                Audio::Sub,
+                "AnimeAv1Scraper"
+            ),
+            PathBuf::from("AnimeAv1Scraper/Re_Zero _ Kara Hajimeru_ - SUB {unknown}")
+        );
+    }
+
+    #[test]
+    fn parses_yt_dlp_telemetry() {
+        assert_eq!(
+            parse_yt_dlp_progress("[download]  45.0% of ~ 100.00MiB at  2.00MiB/s ETA 01:05"),
+            Some(Telemetry {
+                percent: 45.0,
+                total: Some(100 * 1024 * 1024),
+                speed: Some(2 * 1024 * 1024),
+                eta: Some(65),
+            })
+        );
+        assert_eq!(
+            parse_yt_dlp_progress("[download]   3.1% of 1.50GB at Unknown B/s ETA Unknown"),
+            Some(Telemetry {
+                percent: 3.1,
+                total: Some(1_500_000_000),
+                speed: None,
+                eta: None,
+            })
+        );
+        assert_eq!(
+            parse_yt_dlp_progress("[info] Downloading 1 format(s)"),
+            None
+        );
+    }
+
+    #[test]
+    fn splits_the_rate_limit_between_running_jobs() {
+        let rate = RateLimit::default();
+        rate.set(1000);
+        assert_eq!(rate.share_kib(), 1000);
+
+        rate.jobs.store(4, Ordering::Relaxed);
+        assert_eq!(rate.share_kib(), 250);
+    }
+
+    #[test]
+    fn releases_host_slots_on_failover() {
+        let slots = HostSlots::default();
+
+        let first = slots.acquire("a.example.com", 1).unwrap();
+        assert!(slots.is_full("a.example.com", 1));
+        assert!(slots.acquire("a.example.com", 1).is_none());
+        assert!(!slots.is_full("b.example.com", 1));
+        drop(first);
+
+        let second = slots.acquire("b.example.com", 1).unwrap();
+        assert!(!slots.is_full("a.example.com", 1));
+        assert!(slots.is_full("b.example.com", 1));
+        drop(second);
+
+        assert!(!slots.is_full("b.example.com", 1));
+        assert!(!slots.is_full("a.example.com", 0));
+    }
+
+    #[test]
+    fn drops_partial_files_of_another_mirror() {
+        let dir = std::env::temp_dir().join(format!("ani-link-mirror-{}", std::process::id()));
+        let stem = dir.join("show-1");
+        let part = dir.join("show-1.mp4.part");
+
+        claim_partial_files(&stem, "https://a.example.com/1");
+        fs::write(&part, "from a").unwrap();
+
+        claim_partial_files(&stem, "https://a.example.com/1");
+        assert!(part.exists());
+
+        claim_partial_files(&stem, "https://b.example.com/1");
+        assert!(!part.exists());
+        assert_eq!(
+            fs::read_to_string(mirror_marker(&stem)).unwrap(),
+            "https://b.example.com/1"
+        );
 
-    pub const fn progress(&self) -> &AtomicF32 {
-        &self.progress
+        fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,75 @@
+use std::{
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{BufReader, Write},
+    path::PathBuf,
+};
+
+use dirs::{config_dir, state_dir};
+
+use crate::{
+    atomic_file,
+    download::{DownloadItem, DownloadState},
+};
+
+fn dir() -> Option<PathBuf> {
+    let mut path = state_dir().or_else(config_dir)?;
+    path.push("ani-link");
+    Some(path)
+}
+
+fn path() -> Option<PathBuf> {
+    let mut path = dir()?;
+    path.push("downloads.json");
+    Some(path)
+}
+
+fn log_path(id: u64) -> Option<PathBuf> {
+    let mut path = dir()?;
+    path.push("logs");
+    path.push(format!("{id}.log"));
+    Some(path)
+}
+
+pub fn load() -> Vec<DownloadItem> {
+    let Some(file) = path().and_then(|path| File::open(path).ok()) else {
+        return vec![];
+    };
+
+    let mut items: Vec<DownloadItem> = serde_json::from_reader(BufReader::new(file))
+        .unwrap_or_else(|err| {
+            eprintln!("Couldn't read the download queue: {err}");
+            vec![]
+        });
+
+    for item in &mut items {
+        if item.state == DownloadState::Active {
+            item.state = DownloadState::Queued;
+        }
+    }
+
+    items
+}
+
+pub fn save(items: &[DownloadItem]) -> Result<(), Box<dyn Error>> {
+    let path = path().ok_or("State path not found")?;
+    fs::create_dir_all(path.parent().ok_or("State path has no parent")?)?;
+
+    atomic_file::write_json(&path, items)
+}
+
+pub fn log(id: u64, message: &str) -> Result<(), Box<dyn Error>> {
+    let path = log_path(id).ok_or("State path not found")?;
+    fs::create_dir_all(path.parent().ok_or("State path has no parent")?)?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{message}")?;
+
+    Ok(())
+}
+
+pub fn remove_log(id: u64) {
+    if let Some(path) = log_path(id) {
+        let _ = fs::remove_file(path);
+    }
+}
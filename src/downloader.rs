@@ -0,0 +1,498 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use reqwest::{
+    StatusCode, Url,
+    header::{CONTENT_RANGE, ETAG, HeaderMap, IF_RANGE, LAST_MODIFIED, RANGE},
+};
+use tokio::time::{sleep, timeout};
+
+use crate::{
+    download::{Progress, RateLimit},
+    extractor::Stream,
+    http_client::HttpClient,
+    scraper::error::ScraperError,
+};
+
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadError {
+    Http(ScraperError),
+    Io(String),
+    Unsupported(String),
+    Interrupted,
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(err) => write!(f, "{err}"),
+            Self::Io(err) => write!(f, "error de escritura: {err}"),
+            Self::Unsupported(reason) => write!(f, "no soportado: {reason}"),
+            Self::Interrupted => write!(f, "interrumpida"),
+        }
+    }
+}
+
+impl From<ScraperError> for DownloadError {
+    fn from(err: ScraperError) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Http(err.into())
+    }
+}
+
+impl From<io::Error> for DownloadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
+
+pub async fn download(
+    client: &HttpClient,
+    stream: &Stream,
+    stem: &Path,
+    progress: &Progress,
+    rate: &RateLimit,
+) -> Result<PathBuf, DownloadError> {
+    let url = Url::parse(&stream.url).map_err(|err| ScraperError::Parse(err.to_string()))?;
+
+    if let Some(parent) = stem.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if Path::new(url.path())
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("m3u8"))
+    {
+        download_hls(client, stream, url, stem, progress, rate).await
+    } else {
+        let path = stem.with_extension(extension(&url).unwrap_or("mp4"));
+        download_file(client, stream, &path, progress, rate).await?;
+        Ok(path)
+    }
+}
+
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+async fn download_file(
+    client: &HttpClient,
+    stream: &Stream,
+    path: &Path,
+    progress: &Progress,
+    rate: &RateLimit,
+) -> Result<(), DownloadError> {
+    let part = part_path(path);
+    let validator = sidecar(&part, "validator");
+    let mut resume = fs::metadata(&part)
+        .ok()
+        .map(|metadata| metadata.len())
+        .filter(|existing| *existing > 0)
+        .zip(fs::read_to_string(&validator).ok());
+
+    let (mut response, mut file, offset) = loop {
+        let mut headers = stream.headers.clone();
+        if let Some((existing, validator)) = &resume {
+            headers.push((RANGE.to_string(), format!("bytes={existing}-")));
+            headers.push((IF_RANGE.to_string(), validator.clone()));
+        }
+
+        let response = client.response(&stream.url, &headers).await?;
+        let range = content_range(response.headers());
+
+        match (response.status(), &resume) {
+            (StatusCode::PARTIAL_CONTENT, Some((existing, _)))
+                if range.is_some_and(|(start, _)| start == Some(*existing)) =>
+            {
+                let existing = *existing;
+                break (
+                    response,
+                    OpenOptions::new().append(true).open(&part)?,
+                    existing,
+                );
+            }
+            (StatusCode::RANGE_NOT_SATISFIABLE, Some((existing, _))) => {
+                if range.is_some_and(|(_, length)| length == Some(*existing)) {
+                    fs::rename(&part, path)?;
+                    let _ = fs::remove_file(&validator);
+                    return Ok(());
+                }
+                resume = None;
+            }
+            (StatusCode::PARTIAL_CONTENT, Some(_)) => resume = None,
+            (status, _) => {
+                if let Some(err) = ScraperError::from_status(status) {
+                    return Err(err.into());
+                }
+
+                match resource_validator(response.headers()) {
+                    Some(value) => fs::write(&validator, value)?,
+                    None => {
+                        let _ = fs::remove_file(&validator);
+                    }
+                }
+                break (response, File::create(&part)?, 0);
+            }
+        }
+    };
+
+    progress.set_bytes(
+        offset,
+        response.content_length().map(|length| length + offset),
+    );
+
+    while let Some(chunk) = timeout(STALL_TIMEOUT, response.chunk())
+        .await
+        .map_err(|_| ScraperError::Timeout)??
+    {
+        if progress.is_interrupted() {
+            return Err(DownloadError::Interrupted);
+        }
+        file.write_all(&chunk)?;
+        progress.add_bytes(chunk.len() as u64);
+        sleep(rate.delay(chunk.len() as u64)).await;
+    }
+
+    file.flush()?;
+    drop(file);
+    fs::rename(&part, path)?;
+    let _ = fs::remove_file(&validator);
+
+    Ok(())
+}
+
+fn sidecar(part: &Path, extension: &str) -> PathBuf {
+    let mut sidecar = part.as_os_str().to_owned();
+    sidecar.push(".");
+    sidecar.push(extension);
+    PathBuf::from(sidecar)
+}
+
+fn resource_validator(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| {
+            headers
+                .get(LAST_MODIFIED)
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(ToOwned::to_owned)
+}
+
+fn content_range(headers: &HeaderMap) -> Option<(Option<u64>, Option<u64>)> {
+    let (range, length) = headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .trim()
+        .strip_prefix("bytes ")?
+        .split_once('/')?;
+
+    let start = range
+        .split_once('-')
+        .and_then(|(start, _)| start.trim().parse().ok());
+
+    Some((start, length.trim().parse().ok()))
+}
+
+async fn download_hls(
+    client: &HttpClient,
+    stream: &Stream,
+    url: Url,
+    stem: &Path,
+    progress: &Progress,
+    rate: &RateLimit,
+) -> Result<PathBuf, DownloadError> {
+    let mut url = url;
+    let mut playlist = client
+        .text_with_headers(url.as_str(), &stream.headers)
+        .await?;
+
+    if let Some(variant) = best_variant(&playlist, &url) {
+        playlist = client
+            .text_with_headers(variant.as_str(), &stream.headers)
+            .await?;
+        url = variant;
+    }
+
+    let Playlist { init, segments } = parse_playlist(&playlist, &url)?;
+    if segments.is_empty() {
+        return Err(DownloadError::Unsupported("lista HLS vacía".into()));
+    }
+
+    let path = stem.with_extension(if init.is_some() { "mp4" } else { "ts" });
+    let part = part_path(&path);
+    let index = sidecar(&part, "idx");
+
+    let (done, mut written) = fs::read_to_string(&index)
+        .ok()
+        .and_then(|index| {
+            let (done, written) = index.trim().split_once(' ')?;
+            Some((done.parse::<usize>().ok()?, written.parse::<u64>().ok()?))
+        })
+        .filter(|(done, written)| {
+            *done <= segments.len()
+                && fs::metadata(&part).is_ok_and(|metadata| metadata.len() >= *written)
+        })
+        .unwrap_or_default();
+
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&part)?;
+    file.set_len(written)?;
+    drop(file);
+    let mut file = OpenOptions::new().append(true).open(&part)?;
+
+    if done == 0
+        && written == 0
+        && let Some(init) = &init
+    {
+        let bytes = client
+            .bytes_with_headers(init.as_str(), &stream.headers)
+            .await?;
+        file.write_all(&bytes)?;
+        written += bytes.len() as u64;
+        fs::write(&index, format!("0 {written}"))?;
+    }
+
+    for (i, segment) in segments.iter().enumerate().skip(done) {
+        if progress.is_interrupted() {
+            return Err(DownloadError::Interrupted);
+        }
+        let bytes = client
+            .bytes_with_headers(segment.as_str(), &stream.headers)
+            .await?;
+        file.write_all(&bytes)?;
+        written += bytes.len() as u64;
+        fs::write(&index, format!("{} {written}", i + 1))?;
+
+        let estimate = written / (i as u64 + 1) * segments.len() as u64;
+        progress.set_bytes(written, Some(estimate.max(written)));
+        sleep(rate.delay(bytes.len() as u64)).await;
+    }
+
+    file.flush()?;
+    drop(file);
+    fs::rename(&part, &path)?;
+    let _ = fs::remove_file(&index);
+
+    Ok(path)
+}
+
+fn extension(url: &Url) -> Option<&str> {
+    let (_, extension) = url.path().rsplit_once('.')?;
+    (!extension.is_empty()
+        && extension.len() <= 4
+        && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+    .then_some(extension)
+}
+
+fn best_variant(playlist: &str, base: &Url) -> Option<Url> {
+    let mut best: Option<(u64, Url)> = None;
+    let mut lines = playlist.lines().map(str::trim);
+
+    while let Some(line) = lines.next() {
+        let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") else {
+            continue;
+        };
+
+        let bandwidth = attributes
+            .split(',')
+            .find_map(|attribute| attribute.strip_prefix("BANDWIDTH="))
+            .and_then(|bandwidth| bandwidth.parse().ok())
+            .unwrap_or_default();
+
+        let Some(uri) = lines.find(|line| !line.is_empty() && !line.starts_with('#')) else {
+            break;
+        };
+
+        if best.as_ref().is_none_or(|(best, _)| bandwidth > *best)
+            && let Ok(uri) = base.join(uri)
+        {
+            best = Some((bandwidth, uri));
+        }
+    }
+
+    best.map(|(_, uri)| uri)
+}
+
+struct Playlist {
+    init: Option<Url>,
+    segments: Vec<Url>,
+}
+
+fn parse_playlist(playlist: &str, base: &Url) -> Result<Playlist, DownloadError> {
+    let mut init = None;
+    let mut segments = vec![];
+
+    for line in playlist.lines().map(str::trim) {
+        if let Some(key) = line.strip_prefix("#EXT-X-KEY:")
+            && !key.contains("METHOD=NONE")
+        {
+            return Err(DownloadError::Unsupported("HLS cifrado".into()));
+        }
+
+        if let Some(map) = line.strip_prefix("#EXT-X-MAP:") {
+            if map.contains("BYTERANGE=") {
+                return Err(DownloadError::Unsupported("HLS con BYTERANGE".into()));
+            }
+
+            let uri = map
+                .split_once("URI=\"")
+                .and_then(|(_, uri)| uri.split_once('"'))
+                .map(|(uri, _)| uri)
+                .ok_or_else(|| ScraperError::Parse(format!("EXT-X-MAP sin URI: {map}")))?;
+            let uri = base
+                .join(uri)
+                .map_err(|err| ScraperError::Parse(err.to_string()))?;
+
+            if init.as_ref().is_some_and(|init| *init != uri) {
+                return Err(DownloadError::Unsupported(
+                    "HLS con varios segmentos de inicialización".into(),
+                ));
+            }
+            init = Some(uri);
+        }
+
+        if !line.is_empty() && !line.starts_with('#') {
+            segments.push(
+                base.join(line)
+                    .map_err(|err| ScraperError::Parse(err.to_string()))?,
+            );
+        }
+    }
+
+    Ok(Playlist { init, segments })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_highest_bandwidth_variant() {
+        let base = Url::parse("https://cdn.example.com/hls/master.m3u8").unwrap();
+        let playlist = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360\n\
+            360/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2800000,RESOLUTION=1280x720\n\
+            720/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1400000,RESOLUTION=854x480\n\
+            480/index.m3u8\n";
+
+        assert_eq!(
+            best_variant(playlist, &base).unwrap().as_str(),
+            "https://cdn.example.com/hls/720/index.m3u8"
+        );
+        assert!(best_variant("#EXTM3U\nsegment0.ts\n", &base).is_none());
+    }
+
+    #[test]
+    fn resolves_media_segments() {
+        let base = Url::parse("https://cdn.example.com/hls/720/index.m3u8").unwrap();
+        let playlist = "#EXTM3U\n\
+            #EXT-X-TARGETDURATION:10\n\
+            #EXTINF:10.0,\n\
+            segment0.ts\n\
+            #EXTINF:10.0,\n\
+            /other/segment1.ts\n\
+            #EXTINF:4.5,\n\
+            https://edge.example.com/segment2.ts\n\
+            #EXT-X-ENDLIST\n";
+
+        let playlist = parse_playlist(playlist, &base).unwrap();
+
+        assert!(playlist.init.is_none());
+        assert_eq!(
+            playlist
+                .segments
+                .iter()
+                .map(Url::as_str)
+                .collect::<Vec<_>>(),
+            [
+                "https://cdn.example.com/hls/720/segment0.ts",
+                "https://cdn.example.com/other/segment1.ts",
+                "https://edge.example.com/segment2.ts",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_content_ranges() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(content_range(&headers), None);
+
+        headers.insert(CONTENT_RANGE, "bytes 100-199/200".parse().unwrap());
+        assert_eq!(content_range(&headers), Some((Some(100), Some(200))));
+
+        headers.insert(CONTENT_RANGE, "bytes */200".parse().unwrap());
+        assert_eq!(content_range(&headers), Some((None, Some(200))));
+
+        headers.insert(CONTENT_RANGE, "bytes 0-99/*".parse().unwrap());
+        assert_eq!(content_range(&headers), Some((Some(0), None)));
+    }
+
+    #[test]
+    fn prefers_strong_validators() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, "W/\"weak\"".parse().unwrap());
+        headers.insert(
+            LAST_MODIFIED,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(
+            resource_validator(&headers).as_deref(),
+            Some("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+
+        headers.insert(ETAG, "\"strong\"".parse().unwrap());
+        assert_eq!(resource_validator(&headers).as_deref(), Some("\"strong\""));
+    }
+
+    #[test]
+    fn resolves_fmp4_init_segment() {
+        let base = Url::parse("https://cdn.example.com/hls/720/index.m3u8").unwrap();
+        let playlist = "#EXTM3U\n\
+            #EXT-X-MAP:URI=\"init.mp4\"\n\
+            #EXTINF:4.0,\n\
+            segment0.m4s\n\
+            #EXT-X-ENDLIST\n";
+
+        let playlist = parse_playlist(playlist, &base).unwrap();
+
+        assert_eq!(
+            playlist.init.unwrap().as_str(),
+            "https://cdn.example.com/hls/720/init.mp4"
+        );
+        assert_eq!(playlist.segments.len(), 1);
+    }
+
+    #[test]
+    fn rejects_encrypted_playlists() {
+        let base = Url::parse("https://cdn.example.com/index.m3u8").unwrap();
+        let playlist = "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"\nsegment0.ts\n";
+
+        assert!(matches!(
+            parse_playlist(playlist, &base),
+            Err(DownloadError::Unsupported(_))
+        ));
+    }
+}
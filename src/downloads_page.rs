@@ -0,0 +1,284 @@
+use std::{mem, sync::Arc};
+
+use iced::{
+    Element, Event, Length, Padding, Subscription, Task,
+    alignment::Horizontal,
+    event::{self, Status},
+    keyboard::{
+        Event::KeyPressed,
+        Key,
+        key::Named::{ArrowDown, ArrowLeft, ArrowUp, Escape},
+    },
+    never,
+    time::{self, Duration},
+    widget::{
+        Column, Id, Scrollable, column, container,
+        operation::snap_to,
+        rich_text,
+        scrollable::{self, Direction, Scrollbar},
+        span, text,
+    },
+};
+
+use crate::{
+    app, catalogue_cache,
+    config::Config,
+    download::{Command, Download, DownloadState},
+    http_client::HttpClient,
+    list_query_state::ListQueryState,
+    main_menu_page::{self, MainMenuPage},
+    page::{AppUpdate, Page},
+    postprocess,
+    presets::{square_box, transparent_button_cond},
+};
+
+const DOWNLOADS_SCROLLABLE_ID: &str = "downloads_scrollable";
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Click(usize),
+    KeyPressed(Key),
+}
+
+pub struct DownloadsPage {
+    pub config: Config,
+    pub client: HttpClient,
+    pub anime_list: ListQueryState,
+    pub download: Arc<Download>,
+    pub selected: usize,
+}
+
+impl Page for DownloadsPage {
+    fn view(&self) -> iced::Element<'_, app::Message> {
+        let selected = self.selected;
+        let items = self.download.items().clone();
+        let palette = self.config.theme().palette();
+
+        let list: Element<'_, app::Message> = if items.is_empty() {
+            text("No hay descargas")
+                .width(Length::Fill)
+                .align_x(Horizontal::Center)
+                .into()
+        } else {
+            Column::with_children(items.iter().enumerate().map(|(i, item)| {
+                let status = match &item.state {
+                    DownloadState::Active if item.progress.is_processing() => {
+                        item.progress.format()
+                    }
+                    DownloadState::Active => {
+                        format!("{} {}", item.state, item.progress.format())
+                    }
+                    state => state.to_string(),
+                };
+
+                let profile = item
+                    .token
+                    .post_profile
+                    .as_ref()
+                    .map(|profile| format!(" [{profile}]"))
+                    .unwrap_or_default();
+
+                Element::new(
+                    transparent_button_cond(
+                        &format!(
+                            "{} episodio {}{profile} - {status}",
+                            item.token.anime.title, item.token.episode
+                        ),
+                        || selected == i,
+                    )
+                    .on_press(app::Message::Downloads(Message::Click(i))),
+                )
+            }))
+            .into()
+        };
+
+        square_box(
+            column![
+                text("Descargas")
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center),
+                container(
+                    Scrollable::new(list)
+                        .id(Id::new(DOWNLOADS_SCROLLABLE_ID))
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .direction(Direction::Vertical(Scrollbar::new()))
+                )
+                .padding(Padding {
+                    top: 6.0,
+                    right: 6.0,
+                    bottom: 3.0,
+                    left: 6.0
+                }),
+                container(
+                    rich_text![
+                        span("Subir:").color(palette.text),
+                        span(" ↑ K ").color(palette.primary),
+                        span(" Bajar:").color(palette.text),
+                        span(" ↓ J ").color(palette.primary),
+                        span(" Pausar/Reanudar:").color(palette.text),
+                        span(" P ").color(palette.primary),
+                        span(" Reintentar:").color(palette.text),
+                        span(" R ").color(palette.primary),
+                        span(" Cancelar:").color(palette.text),
+                        span(" C ").color(palette.primary),
+                        span(" Perfil:").color(palette.text),
+                        span(" O ").color(palette.primary),
+                        span(" Mover:").color(palette.text),
+                        span(" Mayús+K/J ").color(palette.primary),
+                        span(" Salir:").color(palette.text),
+                        span(" ← H Esc Q").color(palette.primary),
+                    ]
+                    .on_link_click(never)
+                )
+                .align_x(Horizontal::Center)
+                .width(Length::Fill),
+            ]
+            .spacing(3)
+            .padding(3),
+        )
+        .into()
+    }
+
+    fn update(&mut self, message: app::Message) -> AppUpdate {
+        if let app::Message::Downloads(message) = message {
+            match message {
+                Message::Click(index) => {
+                    self.selected = index;
+                    AppUpdate::None
+                }
+                Message::KeyPressed(key) => match key.as_ref() {
+                    Key::Character("j") | Key::Named(ArrowDown) => {
+                        if self.selected + 1 < self.download.items().len() {
+                            self.selected += 1;
+                            return AppUpdate::Task(self.scroll_to_index());
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("k") | Key::Named(ArrowUp) => {
+                        if self.selected > 0 {
+                            self.selected -= 1;
+                            return AppUpdate::Task(self.scroll_to_index());
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("p") => {
+                        if let Some((id, state)) = self.selected_item() {
+                            self.download.send(if state == DownloadState::Paused {
+                                Command::Resume(id)
+                            } else {
+                                Command::Pause(id)
+                            });
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("r") => {
+                        if let Some((id, _)) = self.selected_item() {
+                            self.download.send(Command::Retry(id));
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("c") => {
+                        if let Some((id, _)) = self.selected_item() {
+                            self.download.send(Command::Cancel(id));
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("o") => {
+                        if let Some(item) = self.download.items().get(self.selected) {
+                            let next = postprocess::next_profile(
+                                &self.config.post_profiles,
+                                item.token.post_profile.as_deref(),
+                            );
+                            self.download.send(Command::SetProfile(item.id, next));
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("K") => {
+                        if let Some((id, _)) = self.selected_item()
+                            && self.selected > 0
+                        {
+                            self.download.send(Command::MoveUp(id));
+                            self.selected -= 1;
+                            return AppUpdate::Task(self.scroll_to_index());
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("J") => {
+                        if let Some((id, _)) = self.selected_item()
+                            && self.selected + 1 < self.download.items().len()
+                        {
+                            self.download.send(Command::MoveDown(id));
+                            self.selected += 1;
+                            return AppUpdate::Task(self.scroll_to_index());
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("q" | "h") | Key::Named(ArrowLeft | Escape) => {
+                        AppUpdate::Page(Box::new(MainMenuPage {
+                            catalogue_updated: catalogue_cache::updated(self.config.scraper),
+                            config: mem::take(&mut self.config),
+                            client: mem::take(&mut self.client),
+                            selection: main_menu_page::Selection::Downloads,
+                            anime_list: mem::take(&mut self.anime_list),
+                            download: self.download.clone(),
+                            waiting: false,
+                        }))
+                    }
+                    _ => AppUpdate::None,
+                },
+            }
+        } else if matches!(message, app::Message::Update) {
+            self.selected = self
+                .selected
+                .min(self.download.items().len().saturating_sub(1));
+            AppUpdate::None
+        } else {
+            AppUpdate::None
+        }
+    }
+
+    fn subscription(&self) -> iced::Subscription<app::Message> {
+        Subscription::batch(vec![
+            time::every(Duration::from_millis(100)).map(|_| app::Message::Update),
+            event::listen_with(move |event, status, _| match (event, status) {
+                (Event::Keyboard(KeyPressed { key, .. }), Status::Ignored) => {
+                    Some(app::Message::Downloads(Message::KeyPressed(key)))
+                }
+                _ => None,
+            }),
+        ])
+    }
+
+    fn theme(&self) -> iced::Theme {
+        self.config.theme()
+    }
+}
+
+impl DownloadsPage {
+    fn selected_item(&self) -> Option<(u64, DownloadState)> {
+        self.download
+            .items()
+            .get(self.selected)
+            .map(|item| (item.id, item.state.clone()))
+    }
+
+    fn scroll_to_index(&self) -> Task<app::Message> {
+        let list_len = self.download.items().len();
+
+        if self.selected >= list_len {
+            return Task::none();
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let offset = self.selected as f32 / list_len as f32;
+
+        snap_to(
+            Id::new(DOWNLOADS_SCROLLABLE_ID),
+            scrollable::RelativeOffset {
+                x: 0.0,
+                y: offset.clamp(0.0, 1.0),
+            },
+        )
+    }
+}
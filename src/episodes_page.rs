@@ -1,23 +1,40 @@
 use std::{
+    collections::HashMap,
     mem,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
-    sync::mpsc::channel,
+    sync::{Arc, mpsc::channel},
     thread,
 };
 
 use crate::{
     app,
+    batch_prompt::{BatchPrompt, EpisodeRange, RANGE_INPUT_ID},
     config::Config,
-    download::DownloadToken,
+    download::{Download, DownloadToken},
+    extractor::{self, Stream},
+    http_client::HttpClient,
     image_query_state::ImageQueryState,
+    list_query_state::ListQueryState,
+    media_server,
+    mirror_picker::MirrorPicker,
+    notifications,
     page::{AppUpdate, Page},
+    postprocess,
     presets::{square_box, transparent_button_cond},
-    scraper::anime::Anime,
+    scraper::{
+        ScraperResult,
+        anime::Anime,
+        episode::Episode,
+        mirror::{self, Audio, Mirror, MirrorPreference},
+    },
     search_page::SearchPage,
+    storage::EPISODE_SIZE_ESTIMATE,
 };
 use dirs::{config_dir, state_dir};
 use iced::{
-    Element, Event, Length, Padding, Subscription, Task,
+    Element, Event, Font, Length, Padding, Subscription, Task,
     alignment::Horizontal,
     event::{self, Status},
     keyboard::{
@@ -26,62 +43,114 @@ use iced::{
         key::Named::{ArrowDown, ArrowLeft, ArrowRight, ArrowUp, Enter, Escape},
     },
     never,
-    time::{self, Duration},
+    time::{self, Duration, Instant},
     widget::{
         Column, Id, Scrollable, column, container,
-        operation::snap_to,
-        rich_text,
+        operation::{focus, snap_to},
+        rich_text, row,
         scrollable::{self, Direction, Scrollbar},
-        span,
+        span, stack, text,
     },
 };
 use itertools::Itertools;
 use libmpv2::Mpv;
-use notify_rust::Notification;
-use reqwest::Client;
+use tokio::runtime::Handle;
 
 const EPISODES_SCROLLABLE_ID: &str = "episodes_scrollable";
-pub const WHITELIST: [&str; 3] = ["mp4upload", "ok.ru", "my.mail.ru"];
+const PROBE_RETRY: Duration = Duration::from_secs(30);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum Action {
     Play,
     Stream,
+    Pick,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Click(usize),
     KeyPressed(Key),
-    Retrieved(Action, Vec<String>),
+    Retrieved(Action, usize, ScraperResult<Vec<Mirror>>),
+    Probed(usize, ScraperResult<Vec<Mirror>>),
+    Pinged(String, Option<Duration>),
+    Resolved(Vec<(String, Option<Stream>)>),
+    PickerClick(usize),
+    RangeInput(String),
+    RangeSubmit,
 }
 
 pub struct EpisodesPage {
     pub config: Config,
-    pub client: Client,
+    pub client: HttpClient,
     pub search_query: String,
     pub search_selected: usize,
     pub selected: usize,
     pub anime_list: Vec<Anime>,
     pub anime: Anime,
-    pub episodes: Vec<f64>,
+    pub episodes: Vec<Episode>,
+    pub variants: HashMap<usize, Vec<Audio>>,
+    pub probe_failures: HashMap<usize, Instant>,
+    pub probing: bool,
+    pub picker: Option<MirrorPicker>,
+    pub visual: Option<usize>,
+    pub batch: Option<BatchPrompt>,
+    pub refresh: ListQueryState,
+    pub download: Arc<Download>,
 }
 
 impl Page for EpisodesPage {
+    #[allow(clippy::too_many_lines)]
     fn view(&self) -> iced::Element<'_, crate::app::Message> {
+        let download = self.config.scraper.capabilities().download;
         let selected = self.selected;
-        column![
+        let visual = self.visual_range();
+        let library = self.download.library();
+        let episodes = column![
             square_box(
                 column![
                     container(
                         Scrollable::new(Column::with_children(
-                            self.episodes.iter().enumerate().map(|(i, episode)| {
-                                Element::new(
-                                    transparent_button_cond(&format!("{episode}"), || {
-                                        selected == i
+                            self.episodes.iter().enumerate().flat_map(|(i, episode)| {
+                                let header = (episode.is_special()
+                                    && (i == 0 || !self.episodes[i - 1].is_special()))
+                                .then(|| {
+                                    Element::new(
+                                        text("Especiales")
+                                            .font(Font {
+                                                weight: iced::font::Weight::Bold,
+                                                ..Font::DEFAULT
+                                            })
+                                            .style(|theme: &iced::Theme| text::Style {
+                                                color: Some(theme.palette().primary),
+                                            })
+                                            .width(Length::Fill)
+                                            .align_x(Horizontal::Center),
+                                    )
+                                });
+
+                                let mut label = match self.variants.get(&i) {
+                                    Some(variants) if !variants.is_empty() => format!(
+                                        "{} [{}]",
+                                        episode.label(),
+                                        variants.iter().join("/")
+                                    ),
+                                    _ => episode.label(),
+                                };
+                                if let Some(file) =
+                                    library.get(&self.config, &self.anime.slug, episode)
+                                {
+                                    label.push_str(" [descargado]");
+                                    if library.is_watched(file) {
+                                        label.push_str(" [visto]");
+                                    }
+                                }
+
+                                header.into_iter().chain([Element::new(
+                                    transparent_button_cond(&label, || {
+                                        selected == i || visual.contains(&i)
                                     })
                                     .on_press(app::Message::Episodes(Message::Click(i))),
-                                )
+                                )])
                             })
                         ))
                         .id(Id::new(EPISODES_SCROLLABLE_ID))
@@ -103,10 +172,26 @@ impl Page for EpisodesPage {
                             span(" ↓ J ").color(self.config.theme().palette().primary),
                             span(" Confirmar:").color(self.config.theme().palette().text),
                             span(" → L Enter ").color(self.config.theme().palette().primary),
-                            span(" Descargar:").color(self.config.theme().palette().text),
-                            span(" D ").color(self.config.theme().palette().primary),
+                            span(if download { " Descargar:" } else { "" })
+                                .color(self.config.theme().palette().text),
+                            span(if download { " D " } else { "" })
+                                .color(self.config.theme().palette().primary),
+                            span(if download { " Selección:" } else { "" })
+                                .color(self.config.theme().palette().text),
+                            span(if download { " V " } else { "" })
+                                .color(self.config.theme().palette().primary),
+                            span(if download { " Rango:" } else { "" })
+                                .color(self.config.theme().palette().text),
+                            span(if download { " R " } else { "" })
+                                .color(self.config.theme().palette().primary),
+                            span(" Visto:").color(self.config.theme().palette().text),
+                            span(" W ").color(self.config.theme().palette().primary),
+                            span(" Perfil:").color(self.config.theme().palette().text),
+                            span(" P ").color(self.config.theme().palette().primary),
                             span(" Syncplay:").color(self.config.theme().palette().text),
                             span(" S ").color(self.config.theme().palette().primary),
+                            span(" Mirrors:").color(self.config.theme().palette().text),
+                            span(" M ").color(self.config.theme().palette().primary),
                             span(" Salir:").color(self.config.theme().palette().text),
                             span(" ← H Esc Q").color(self.config.theme().palette().primary),
                         ]
@@ -119,6 +204,18 @@ impl Page for EpisodesPage {
                 .padding(3)
             )
             .width(Length::Fill),
+        ];
+
+        stack![
+            episodes,
+            self.picker.as_ref().map_or_else(
+                || row![].into(),
+                |picker| picker.view(&self.preference(), self.config.theme().palette())
+            ),
+            self.batch.as_ref().map_or_else(
+                || row![].into(),
+                |batch| batch.view(self.config.theme().palette())
+            )
         ]
         .into()
     }
@@ -127,37 +224,121 @@ impl Page for EpisodesPage {
     fn update(&mut self, message: crate::app::Message) -> AppUpdate {
         if let app::Message::Episodes(message) = message {
             match message {
-                Message::Retrieved(action, mirrors) => {
+                Message::Retrieved(action, index, Ok(mirrors)) => {
+                    self.variants.insert(index, mirror::variants(&mirrors));
+
                     match action {
-                        Action::Play => self.play_episode(mirrors),
-                        Action::Stream => stream_episode(mirrors),
+                        Action::Play => self.play_episode(
+                            self.rank(index, mirrors)
+                                .into_iter()
+                                .map(|mirror| mirror.url)
+                                .collect(),
+                        ),
+                        Action::Stream => {
+                            return AppUpdate::Task(stream_episode(
+                                &self.client,
+                                self.rank(index, mirrors),
+                            ));
+                        }
+                        Action::Pick => {
+                            let picker = MirrorPicker::new(
+                                format!("Mirrors del episodio {}", self.episodes[index].label()),
+                                mirrors,
+                                &self.preference(),
+                            );
+                            let task = picker.probe_all(&self.client);
+                            self.picker = Some(picker);
+                            return AppUpdate::Task(task);
+                        }
+                    }
+                    AppUpdate::None
+                }
+                Message::Retrieved(_, index, Err(err)) => {
+                    let episode = &self.episodes[index];
+
+                    let body = if err.is_retryable() {
+                        format!(
+                            "No se han podido obtener los mirrors del episodio {episode}, inténtalo de nuevo más tarde ({err})"
+                        )
+                    } else {
+                        format!(
+                            "No se han podido obtener los mirrors del episodio {episode}: {err}"
+                        )
+                    };
+
+                    notifications::notify(&self.anime.slug, &body, vec![]);
+
+                    AppUpdate::None
+                }
+                Message::Probed(index, result) => {
+                    self.probing = false;
+                    match result {
+                        Ok(mirrors) => {
+                            self.variants.insert(index, mirror::variants(&mirrors));
+                            self.probe_failures.remove(&index);
+                        }
+                        Err(_) => {
+                            self.probe_failures.insert(index, Instant::now());
+                        }
+                    }
+                    AppUpdate::None
+                }
+                Message::Pinged(url, latency) => {
+                    if let Some(picker) = &mut self.picker {
+                        picker.set_probe(&url, latency);
+                    }
+                    AppUpdate::None
+                }
+                Message::Resolved(streams) => {
+                    launch_syncplay(streams);
+                    AppUpdate::None
+                }
+                Message::PickerClick(index) => {
+                    if let Some(picker) = &mut self.picker {
+                        if picker.selected != index {
+                            picker.selected = index;
+                            return AppUpdate::None;
+                        }
+                        return AppUpdate::Task(self.pick(Action::Play));
+                    }
+                    AppUpdate::None
+                }
+                Message::KeyPressed(key) if self.picker.is_some() => {
+                    AppUpdate::Task(self.picker_key(&key))
+                }
+                Message::RangeInput(text) => {
+                    if let Some(BatchPrompt::Range { input, error }) = &mut self.batch {
+                        *input = text;
+                        *error = None;
                     }
                     AppUpdate::None
                 }
+                Message::RangeSubmit => {
+                    if let Some(BatchPrompt::Range { input, error }) = &mut self.batch {
+                        match input.parse::<EpisodeRange>() {
+                            Ok(range) => {
+                                let indices = self
+                                    .episodes
+                                    .iter()
+                                    .positions(|episode| {
+                                        !episode.is_special() && range.contains(episode.number)
+                                    })
+                                    .collect_vec();
+                                self.confirm_batch(indices);
+                            }
+                            Err(err) => *error = Some(err),
+                        }
+                    }
+                    AppUpdate::None
+                }
+                Message::KeyPressed(key) if self.batch.is_some() => self.batch_key(&key),
                 Message::Click(index) => {
                     if self.selected != index {
                         self.selected = index;
                         return AppUpdate::None;
                     }
 
-                    let anime = self.anime.clone();
-                    let scraper = self.config.scraper;
-                    let client = self.client.clone();
-                    let episode = self.episodes[self.selected];
-
-                    AppUpdate::Task(Task::perform(
-                        async move {
-                            let mirrors = scraper
-                                .try_get_mirrors(&client, &anime.names[1], episode)
-                                .await
-                                .unwrap();
-
-                            (Action::Play, mirrors)
-                        },
-                        |(action, mirrors)| {
-                            app::Message::Episodes(Message::Retrieved(action, mirrors))
-                        },
-                    ))
+                    self.play_selected()
                 }
                 Message::KeyPressed(key) => match key.as_ref() {
                     Key::Character("j") | Key::Named(ArrowDown) => {
@@ -174,76 +355,94 @@ impl Page for EpisodesPage {
                         }
                         AppUpdate::None
                     }
-                    Key::Character("l") | Key::Named(ArrowRight | Enter) => {
-                        let anime = self.anime.clone();
-                        let scraper = self.config.scraper;
-                        let client = self.client.clone();
-                        let episode = self.episodes[self.selected];
-
-                        AppUpdate::Task(Task::perform(
-                            async move {
-                                let mirrors = scraper
-                                    .try_get_mirrors(&client, &anime.names[1], episode)
-                                    .await
-                                    .unwrap();
-
-                                (Action::Play, mirrors)
-                            },
-                            |(action, mirrors)| {
-                                app::Message::Episodes(Message::Retrieved(action, mirrors))
-                            },
-                        ))
+                    Key::Character("l") | Key::Named(ArrowRight | Enter) => self.play_selected(),
+                    Key::Character("w") => {
+                        if let Some(file) = self.local_file() {
+                            let mut library = self.download.library();
+                            let watched = library.is_watched(&file);
+                            library.set_watched(&file, !watched);
+                        }
+                        AppUpdate::None
+                    }
+                    Key::Character("v" | "r" | "d")
+                        if !self.config.scraper.capabilities().download =>
+                    {
+                        AppUpdate::None
+                    }
+                    Key::Character("v") => {
+                        self.visual = match self.visual {
+                            Some(_) => None,
+                            None => Some(self.selected),
+                        };
+                        AppUpdate::None
+                    }
+                    Key::Character("r") => {
+                        self.visual = None;
+                        self.batch = Some(BatchPrompt::range());
+                        AppUpdate::Task(focus(Id::new(RANGE_INPUT_ID)))
+                    }
+                    Key::Character("d") if self.visual.is_some() => {
+                        let indices = self.visual_range().collect_vec();
+                        self.visual = None;
+                        self.confirm_batch(indices);
+                        AppUpdate::None
+                    }
+                    Key::Named(Escape) if self.visual.is_some() => {
+                        self.visual = None;
+                        AppUpdate::None
                     }
                     Key::Character("d") => {
-                        let name = self.anime.names[0].clone();
-                        let slug = self.anime.names[1].clone();
-                        let episode = self.episodes[self.selected];
+                        let anime = self.anime.clone();
+                        let episode = self.episodes[self.selected].clone();
+                        let special_index = media_server::special_index(&self.episodes, &episode);
+                        let preference = self.preference();
+
+                        if let Some(file) = self.local_file() {
+                            let folder = file.parent().map(Path::to_path_buf);
+                            notifications::notify(
+                                &anime.slug,
+                                &format!("El episodio {episode} de {anime} ya está descargado"),
+                                [
+                                    Some(notifications::Action::Play(file)),
+                                    folder.map(notifications::Action::OpenFolder),
+                                ]
+                                .into_iter()
+                                .flatten()
+                                .collect(),
+                            );
+                            return AppUpdate::None;
+                        }
 
-                        let _ = Notification::new()
-                            .summary("Ani-link")
-                            .body(format!(r"Añadiendo episodio {episode} de {name} a la cola de descargas...").as_str())
-                            .show()
-                            .is_ok();
+                        notifications::notify(
+                            &anime.slug,
+                            &format!(
+                                "Añadiendo episodio {episode} de {anime} a la cola de descargas..."
+                            ),
+                            vec![],
+                        );
 
                         AppUpdate::Task(Task::perform(
                             async move {
                                 DownloadToken {
-                                    name,
-                                    slug,
+                                    anime,
                                     episode,
+                                    special_index,
+                                    preference,
+                                    post_profile: None,
                                 }
                             },
                             |token| app::Message::Download(vec![token]),
                         ))
                     }
-                    Key::Character("s") => {
-                        let anime = self.anime.clone();
-                        let scraper = self.config.scraper;
-                        let client = self.client.clone();
-                        let episode = self.episodes[self.selected];
-
-                        AppUpdate::Task(Task::perform(
-                            async move {
-                                let mirrors = scraper
-                                    .try_get_mirrors(&client, &anime.names[1], episode)
-                                    .await
-                                    .unwrap();
-
-                                (Action::Stream, mirrors)
-                            },
-                            |(action, mirrors)| {
-                                app::Message::Episodes(Message::Retrieved(action, mirrors))
-                            },
-                        ))
-                    }
+                    Key::Character("p") => AppUpdate::Task(self.cycle_profile()),
+                    Key::Character("s") => AppUpdate::Task(self.retrieve_mirrors(Action::Stream)),
+                    Key::Character("m") => AppUpdate::Task(self.retrieve_mirrors(Action::Pick)),
                     Key::Character("q" | "h") | Key::Named(ArrowLeft | Escape) => {
-                        let image_query = ImageQueryState::spawn(
-                            self.client.clone(),
-                            self.anime_list
-                                .first()
-                                .expect("No animes found")
-                                .image_url
-                                .clone(),
+                        let image_query = self.anime_list.first().map_or_else(
+                            ImageQueryState::default,
+                            |anime| {
+                                ImageQueryState::spawn(self.client.clone(), anime.image_url.clone())
+                            },
                         );
 
                         let mut page = SearchPage {
@@ -254,6 +453,8 @@ impl Page for EpisodesPage {
                             selected: self.search_selected,
                             filtered_list: mem::take(&mut self.anime_list),
                             image: image_query,
+                            refresh: mem::take(&mut self.refresh),
+                            download: self.download.clone(),
                         };
 
                         page.fuzzy();
@@ -263,6 +464,16 @@ impl Page for EpisodesPage {
                     _ => AppUpdate::None,
                 },
             }
+        } else if matches!(message, app::Message::Update)
+            && !self.probing
+            && !self.variants.contains_key(&self.selected)
+            && self
+                .probe_failures
+                .get(&self.selected)
+                .is_none_or(|failed| failed.elapsed() >= PROBE_RETRY)
+        {
+            self.probing = true;
+            AppUpdate::Task(self.probe_variants())
         } else {
             AppUpdate::None
         }
@@ -286,6 +497,301 @@ impl Page for EpisodesPage {
 }
 
 impl EpisodesPage {
+    fn retrieve_mirrors(&self, action: Action) -> Task<app::Message> {
+        let anime = self.anime.clone();
+        let scraper = self.config.scraper;
+        let client = self.client.clone();
+        let index = self.selected;
+        let episode = self.episodes[index].number;
+
+        Task::perform(
+            async move {
+                let mut retries = 3;
+                let mirrors = loop {
+                    match scraper.try_get_mirrors(&client, &anime.slug, episode).await {
+                        Err(err) if err.is_retryable() && retries > 0 => {
+                            retries -= 1;
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                        }
+                        result => break result,
+                    }
+                };
+
+                (action, mirrors)
+            },
+            move |(action, mirrors)| {
+                app::Message::Episodes(Message::Retrieved(action, index, mirrors))
+            },
+        )
+    }
+
+    fn probe_variants(&self) -> Task<app::Message> {
+        let slug = self.anime.slug.clone();
+        let scraper = self.config.scraper;
+        let client = self.client.clone();
+        let index = self.selected;
+        let episode = self.episodes[index].number;
+
+        Task::perform(
+            async move { scraper.try_get_mirrors(&client, &slug, episode).await },
+            move |mirrors| app::Message::Episodes(Message::Probed(index, mirrors)),
+        )
+    }
+
+    fn local_file(&self) -> Option<PathBuf> {
+        self.download
+            .library()
+            .get(
+                &self.config,
+                &self.anime.slug,
+                &self.episodes[self.selected],
+            )
+            .cloned()
+    }
+
+    fn play_selected(&self) -> AppUpdate {
+        if let Some(file) = self.local_file() {
+            self.play_episode(vec![file.to_string_lossy().into_owned()]);
+            return AppUpdate::None;
+        }
+
+        AppUpdate::Task(self.retrieve_mirrors(Action::Play))
+    }
+
+    fn visual_range(&self) -> RangeInclusive<usize> {
+        let anchor = self.visual.unwrap_or(self.selected);
+        anchor.min(self.selected)..=anchor.max(self.selected)
+    }
+
+    fn confirm_batch(&mut self, indices: Vec<usize>) {
+        let library = self.download.library();
+        let (local, indices): (Vec<_>, Vec<_>) = indices.into_iter().partition(|index| {
+            library
+                .get(&self.config, &self.anime.slug, &self.episodes[*index])
+                .is_some()
+        });
+        let average = library
+            .average_size(&self.config, &self.anime.slug)
+            .unwrap_or(EPISODE_SIZE_ESTIMATE);
+        drop(library);
+
+        if indices.is_empty() {
+            self.batch = None;
+            notifications::notify(
+                &self.anime.slug,
+                &if local.is_empty() {
+                    "No hay episodios que descargar en ese rango".to_owned()
+                } else {
+                    format!(
+                        "Los episodios seleccionados de {} ya están descargados",
+                        self.anime
+                    )
+                },
+                vec![],
+            );
+            return;
+        }
+
+        self.batch = Some(BatchPrompt::Confirm {
+            size: average * indices.len() as u64,
+            indices,
+            skipped: local.len(),
+        });
+    }
+
+    fn batch_key(&mut self, key: &Key) -> AppUpdate {
+        match (key.as_ref(), &self.batch) {
+            (
+                Key::Character("y") | Key::Named(Enter),
+                Some(BatchPrompt::Confirm { indices, .. }),
+            ) => {
+                let preference = self.preference();
+                let tokens = indices
+                    .iter()
+                    .map(|index| DownloadToken {
+                        anime: self.anime.clone(),
+                        episode: self.episodes[*index].clone(),
+                        special_index: media_server::special_index(
+                            &self.episodes,
+                            &self.episodes[*index],
+                        ),
+                        preference: preference.clone(),
+                        post_profile: None,
+                    })
+                    .collect_vec();
+                self.batch = None;
+
+                notifications::notify(
+                    &self.anime.slug,
+                    &format!(
+                        "Añadiendo {} episodios de {} a la cola de descargas...",
+                        tokens.len(),
+                        self.anime.title
+                    ),
+                    vec![],
+                );
+
+                AppUpdate::Task(Task::done(app::Message::Download(tokens)))
+            }
+            (Key::Character("n" | "q"), Some(BatchPrompt::Confirm { .. }))
+            | (Key::Named(Escape), _) => {
+                self.batch = None;
+                AppUpdate::None
+            }
+            _ => AppUpdate::None,
+        }
+    }
+
+    fn preference(&self) -> MirrorPreference {
+        self.config.mirror_preference(&self.anime.slug)
+    }
+
+    fn rank(&self, index: usize, mirrors: Vec<Mirror>) -> Vec<Mirror> {
+        let preferred = self.config.audio;
+
+        if self.config.scraper.capabilities().dub
+            && !mirrors.is_empty()
+            && mirrors.iter().all(|mirror| mirror.audio != preferred)
+        {
+            notifications::notify(
+                &self.anime.slug,
+                &format!(
+                    "El episodio {} no está disponible en {preferred}, usando otra versión",
+                    self.episodes[index]
+                ),
+                vec![],
+            );
+        }
+
+        self.preference().rank(mirrors)
+    }
+
+    fn picker_key(&mut self, key: &Key) -> Task<app::Message> {
+        let Some(picker) = &mut self.picker else {
+            return Task::none();
+        };
+
+        match key.as_ref() {
+            Key::Character("j") | Key::Named(ArrowDown) => picker.next(),
+            Key::Character("k") | Key::Named(ArrowUp) => picker.prev(),
+            Key::Character("l") | Key::Named(ArrowRight | Enter) => {
+                return self.pick(Action::Play);
+            }
+            Key::Character("s") => return self.pick(Action::Stream),
+            Key::Character("p") => return self.toggle_pin(),
+            Key::Character("K") => return self.move_host(true),
+            Key::Character("J") => return self.move_host(false),
+            Key::Character("q" | "h" | "m") | Key::Named(ArrowLeft | Escape) => {
+                self.picker = None;
+            }
+            _ => {}
+        }
+
+        Task::none()
+    }
+
+    fn pick(&self, action: Action) -> Task<app::Message> {
+        let Some(mirror) = self.picker.as_ref().and_then(MirrorPicker::selected) else {
+            return Task::none();
+        };
+
+        match action {
+            Action::Play => self.play_episode(vec![mirror.url.clone()]),
+            Action::Stream => return stream_episode(&self.client, vec![mirror.clone()]),
+            Action::Pick => {}
+        }
+
+        Task::none()
+    }
+
+    fn toggle_pin(&mut self) -> Task<app::Message> {
+        let Some(host) = self
+            .picker
+            .as_ref()
+            .and_then(MirrorPicker::selected)
+            .and_then(Mirror::host)
+        else {
+            return Task::none();
+        };
+
+        if self.config.pinned_hosts.get(&self.anime.slug) == Some(&host) {
+            self.config.pinned_hosts.remove(&self.anime.slug);
+        } else {
+            self.config
+                .pinned_hosts
+                .insert(self.anime.slug.clone(), host);
+        }
+
+        self.save_preference()
+    }
+
+    fn move_host(&mut self, up: bool) -> Task<app::Message> {
+        let preference = self.preference();
+        let Some(mirror) = self.picker.as_ref().and_then(MirrorPicker::selected) else {
+            return Task::none();
+        };
+
+        let Some(index) = preference.host_rank(mirror).or_else(|| {
+            self.config.hosts.push(mirror.host()?);
+            Some(self.config.hosts.len() - 1)
+        }) else {
+            return Task::none();
+        };
+
+        let target = if up {
+            index.saturating_sub(1)
+        } else {
+            (index + 1).min(self.config.hosts.len() - 1)
+        };
+        self.config.hosts.swap(index, target);
+
+        self.save_preference()
+    }
+
+    fn cycle_profile(&mut self) -> Task<app::Message> {
+        let next = postprocess::next_profile(
+            &self.config.post_profiles,
+            self.config
+                .anime_post_profiles
+                .get(&self.anime.slug)
+                .map(String::as_str),
+        );
+
+        let body = if let Some(next) = &next {
+            self.config
+                .anime_post_profiles
+                .insert(self.anime.slug.clone(), next.clone());
+            format!(
+                "Se aplicará el perfil \"{next}\" a las descargas de {}",
+                self.anime
+            )
+        } else {
+            self.config.anime_post_profiles.remove(&self.anime.slug);
+            format!("Las descargas de {} no se procesarán", self.anime)
+        };
+
+        if let Err(err) = self.config.save() {
+            eprintln!("Couldn't save the post-processing profile: {err}");
+        }
+
+        notifications::notify(&self.anime.slug, &body, vec![]);
+
+        Task::done(app::Message::Config(Box::new(self.config.clone())))
+    }
+
+    fn save_preference(&mut self) -> Task<app::Message> {
+        if let Err(err) = self.config.save() {
+            eprintln!("Couldn't save the mirror preference: {err}");
+        }
+
+        let preference = self.preference();
+        if let Some(picker) = &mut self.picker {
+            picker.sort(&preference);
+        }
+
+        Task::done(app::Message::Config(Box::new(self.config.clone())))
+    }
+
     fn scroll_to_index(&self) -> Task<app::Message> {
         let list_len = self.episodes.len();
 
@@ -305,96 +811,139 @@ impl EpisodesPage {
         )
     }
 
-    fn play_episode(&self, mirrors: Vec<String>) {
-        let viewable = mirrors
-            .into_iter()
-            .filter(|mirror| WHITELIST.iter().any(|elem| mirror.contains(elem)))
-            .collect_vec();
-
-        let success = viewable.into_iter().all(|mirror| {
-            let _ = Notification::new()
-                .summary("Ani-link")
-                .body(format!(r#"Abriendo "{mirror}" en mpv, por favor, espera."#).as_str())
-                .show()
-                .is_ok();
-
-            let (tx, rx) = channel();
-            let mirror_clone = mirror;
-            let save_on_quit = self.config.save_on_quit;
-
-            thread::spawn(move || {
-                let mut sent = false;
-                let mirror = mirror_clone;
-                let mpv = Mpv::new().unwrap();
-
-                let mut watch_later = state_dir().or_else(config_dir).unwrap();
-                watch_later.push("mpv/watch_later");
-                let watch_later = watch_later.as_path().to_str().unwrap();
-
-                mpv.set_property("osc", true).unwrap();
-                mpv.set_property("watch-later-directory", watch_later)
-                    .unwrap();
-                mpv.set_property("input-default-bindings", true).unwrap();
-                mpv.set_property("force-window", true).unwrap();
-                mpv.set_property("keep-open", "no").unwrap();
-                mpv.set_property("resume-playback", "yes").unwrap();
-                mpv.set_property("idle", "no").unwrap();
-                mpv.set_property(
-                    "save-position-on-quit",
-                    if save_on_quit { "yes" } else { "no" },
-                )
+    fn play_episode(&self, targets: Vec<String>) {
+        play_episode(
+            targets,
+            &self.client,
+            &self.download,
+            self.config.save_on_quit,
+        );
+    }
+}
+
+pub fn play_episode(
+    targets: Vec<String>,
+    client: &HttpClient,
+    download: &Arc<Download>,
+    save_on_quit: bool,
+) {
+    let success = targets.into_iter().any(|mirror| {
+        notifications::notify(
+            notifications::PLAYER,
+            &format!(r#"Abriendo "{mirror}" en mpv, por favor, espera."#),
+            vec![],
+        );
+
+        let (tx, rx) = channel();
+        let local = Path::new(&mirror).is_file().then(|| PathBuf::from(&mirror));
+        let download = download.clone();
+        let mirror_clone = mirror;
+        let client = client.clone();
+        let handle = Handle::current();
+
+        thread::spawn(move || {
+            let mut sent = false;
+            let mirror = mirror_clone;
+            let stream = handle.block_on(extractor::resolve(&client, &mirror));
+            let mpv = Mpv::new().unwrap();
+
+            let mut watch_later = state_dir().or_else(config_dir).unwrap();
+            watch_later.push("mpv/watch_later");
+            let watch_later = watch_later.as_path().to_str().unwrap();
+
+            mpv.set_property("osc", true).unwrap();
+            mpv.set_property("watch-later-directory", watch_later)
                 .unwrap();
-                mpv.command("loadfile", &[&mirror, "replace"]).unwrap();
-                loop {
-                    if let Some(Ok(event)) = mpv.wait_event(-1.0) {
-                        match event {
-                            libmpv2::events::Event::EndFile(reason) => match reason {
-                                0 | 3 => break,
-                                4 if !sent => {
-                                    let _ = tx.send(false).is_ok();
-                                    break;
-                                }
-                                _ if !sent => {
-                                    let _ = tx.send(true).is_ok();
-                                    sent = true;
+            mpv.set_property("input-default-bindings", true).unwrap();
+            mpv.set_property("force-window", true).unwrap();
+            mpv.set_property("keep-open", "no").unwrap();
+            mpv.set_property("resume-playback", "yes").unwrap();
+            mpv.set_property("idle", "no").unwrap();
+            mpv.set_property(
+                "save-position-on-quit",
+                if save_on_quit { "yes" } else { "no" },
+            )
+            .unwrap();
+
+            let target = if let Some(stream) = stream {
+                mpv.set_property("http-header-fields", stream.header_fields())
+                    .unwrap();
+                mpv.set_property("ytdl", false).unwrap();
+                stream.url
+            } else {
+                mirror
+            };
+
+            mpv.command("loadfile", &[&target, "replace"]).unwrap();
+            loop {
+                if let Some(Ok(event)) = mpv.wait_event(-1.0) {
+                    match event {
+                        libmpv2::events::Event::EndFile(reason) => match reason {
+                            0 | 3 => {
+                                if reason == 0
+                                    && let Some(file) = &local
+                                {
+                                    download.library().set_watched(file, true);
                                 }
-                                _ => {}
-                            },
-                            libmpv2::events::Event::PlaybackRestart if !sent => {
-                                let _ = tx.send(true).is_ok();
-                                sent = true;
+                                break;
                             }
-                            libmpv2::events::Event::Shutdown if !sent => {
+                            4 if !sent => {
                                 let _ = tx.send(false).is_ok();
                                 break;
                             }
+                            _ if !sent => {
+                                let _ = tx.send(true).is_ok();
+                                sent = true;
+                            }
                             _ => {}
+                        },
+                        libmpv2::events::Event::PlaybackRestart if !sent => {
+                            let _ = tx.send(true).is_ok();
+                            sent = true;
+                        }
+                        libmpv2::events::Event::Shutdown if !sent => {
+                            let _ = tx.send(false).is_ok();
+                            break;
                         }
+                        _ => {}
                     }
                 }
-                mpv.command("quit", &[]).unwrap();
-            });
-
-            rx.recv().unwrap_or(false)
+            }
+            mpv.command("quit", &[]).unwrap();
         });
 
-        if !success {
-            let _ = Notification::new()
-                .summary("Ani-link")
-                .body("No se ha podido abrir mpv")
-                .show()
-                .is_ok();
-        }
+        rx.recv().unwrap_or(false)
+    });
+
+    if !success {
+        notifications::notify(notifications::PLAYER, "No se ha podido abrir mpv", vec![]);
     }
 }
 
-fn stream_episode(mirrors: Vec<String>) {
-    let viewable = mirrors
-        .into_iter()
-        .filter(|mirror| WHITELIST.iter().any(|elem| mirror.contains(elem)))
-        .collect_vec();
+fn stream_episode(client: &HttpClient, mirrors: Vec<Mirror>) -> Task<app::Message> {
+    let client = client.clone();
+
+    Task::perform(
+        async move {
+            let mut streams = Vec::with_capacity(mirrors.len());
+            let mut resolved = false;
+            for mirror in mirrors {
+                let stream = if resolved {
+                    None
+                } else {
+                    extractor::resolve(&client, &mirror.url).await
+                };
+                resolved |= stream.is_some();
+                streams.push((mirror.url, stream));
+            }
+            streams
+        },
+        |streams| app::Message::Episodes(Message::Resolved(streams)),
+    )
+}
 
-    let success = viewable.into_iter().any(|mirror| {
+fn launch_syncplay(streams: Vec<(String, Option<Stream>)>) {
+    let success = streams.into_iter().any(|(mirror, stream)| {
         let mut command = Command::new(format!(
             "syncplay{}",
             if cfg!(target_os = "windows") {
@@ -404,8 +953,15 @@ fn stream_episode(mirrors: Vec<String>) {
             }
         ));
 
+        match stream {
+            Some(stream) => command
+                .arg(&stream.url)
+                .arg("--")
+                .arg(format!("--http-header-fields={}", stream.header_fields())),
+            None => command.arg(mirror),
+        };
+
         command
-            .arg(mirror)
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .spawn()
@@ -413,10 +969,10 @@ fn stream_episode(mirrors: Vec<String>) {
     });
 
     if !success {
-        let _ = Notification::new()
-            .summary("Ani-link")
-            .body("No se ha podido abrir syncplay")
-            .show()
-            .is_ok();
+        notifications::notify(
+            notifications::PLAYER,
+            "No se ha podido abrir syncplay",
+            vec![],
+        );
     }
 }
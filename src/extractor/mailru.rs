@@ -0,0 +1,132 @@
+use iced::futures::{FutureExt, future::BoxFuture};
+use serde_json::Value;
+
+use crate::{
+    http_client::HttpClient,
+    scraper::{ScraperResult, error::ScraperError, js_value::JsValue},
+};
+
+use super::{Extractor, Stream, absolute, not_found};
+
+const REFERER: &str = "https://my.mail.ru/";
+
+pub struct MailRuExtractor;
+
+impl Extractor for MailRuExtractor {
+    fn matches(&self, host: &str) -> bool {
+        host == "my.mail.ru" || host == "videoapi.my.mail.ru"
+    }
+
+    fn extract<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        url: &'a str,
+    ) -> BoxFuture<'a, ScraperResult<Stream>> {
+        async move {
+            let body = client.text(url).await?;
+            let metadata_url = metadata_url(&body, url)?;
+            let (metadata, cookies) = client.text_with_cookies(&metadata_url).await?;
+
+            parse_metadata(&metadata, &cookies)
+        }
+        .boxed()
+    }
+}
+
+fn metadata_url(body: &str, url: &str) -> ScraperResult<String> {
+    if let Ok(metadata_url) = JsValue::find(body, "metadataUrl")
+        && let Some(metadata_url) = metadata_url.as_str()
+    {
+        return Ok(absolute(metadata_url));
+    }
+
+    let id = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.trim_end_matches(".html").rsplit('/').next())
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+        .ok_or_else(|| not_found("my.mail.ru video id"))?;
+
+    Ok(format!("https://my.mail.ru/+/video/meta/{id}"))
+}
+
+fn parse_metadata(metadata: &str, cookies: &[String]) -> ScraperResult<Stream> {
+    let metadata: Value =
+        serde_json::from_str(metadata).map_err(|err| ScraperError::Parse(err.to_string()))?;
+
+    let url = metadata["videos"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice)
+        .iter()
+        .filter_map(|video| {
+            let quality = video["key"]
+                .as_str()?
+                .trim_end_matches('p')
+                .parse::<u32>()
+                .unwrap_or_default();
+            Some((quality, video["url"].as_str()?))
+        })
+        .max_by_key(|(quality, _)| *quality)
+        .map(|(_, url)| url)
+        .ok_or_else(|| not_found("my.mail.ru video"))?;
+
+    let mut headers = vec![("Referer".to_owned(), REFERER.to_owned())];
+    let cookies = cookies
+        .iter()
+        .filter(|cookie| cookie.starts_with("video_key="))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !cookies.is_empty() {
+        headers.push(("Cookie".into(), cookies.join("; ")));
+    }
+
+    Ok(Stream {
+        url: absolute(url),
+        headers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_metadata_url() {
+        let body = include_str!("../../tests/fixtures/extractor/mailru.html");
+
+        assert_eq!(
+            metadata_url(body, "https://my.mail.ru/video/embed/7386548211806208410").unwrap(),
+            "https://my.mail.ru/+/video/meta/7386548211806208410"
+        );
+    }
+
+    #[test]
+    fn builds_metadata_url_from_embed() {
+        assert_eq!(
+            metadata_url("", "https://my.mail.ru/video/embed/7386548211806208410").unwrap(),
+            "https://my.mail.ru/+/video/meta/7386548211806208410"
+        );
+        assert!(metadata_url("", "https://my.mail.ru/video/embed/").is_err());
+    }
+
+    #[test]
+    fn extracts_best_quality_with_cookie() {
+        let stream = parse_metadata(
+            include_str!("../../tests/fixtures/extractor/mailru_meta.json"),
+            &["video_key=a1b2c3d4e5".into(), "mrcu=0F3A".into()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            stream.url,
+            "https://cdn28.my.mail.ru/v/61234567.mp4?slave[]=s%3Ahttp%3A%2F%2F127.0.0.1%3A5010%2F61234567-v.mp4&p=f&expire_at=1729450000&touch=1729363600&reg=202&sign=3f1c9b1e"
+        );
+        assert_eq!(
+            stream.headers,
+            vec![
+                ("Referer".into(), REFERER.into()),
+                ("Cookie".into(), "video_key=a1b2c3d4e5".into())
+            ]
+        );
+    }
+}
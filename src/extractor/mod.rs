@@ -0,0 +1,79 @@
+use iced::futures::future::BoxFuture;
+use reqwest::Url;
+
+use crate::{
+    http_client::HttpClient,
+    scraper::{ScraperResult, error::ScraperError},
+};
+
+pub mod mailru;
+pub mod mp4upload;
+pub mod okru;
+mod packer;
+
+pub static EXTRACTORS: &[&dyn Extractor] = &[
+    &mp4upload::Mp4UploadExtractor,
+    &okru::OkRuExtractor,
+    &mailru::MailRuExtractor,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stream {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Stream {
+    pub fn header_fields(&self) -> String {
+        self.headers
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+pub trait Extractor: Send + Sync {
+    fn matches(&self, host: &str) -> bool;
+    fn extract<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        url: &'a str,
+    ) -> BoxFuture<'a, ScraperResult<Stream>>;
+}
+
+pub async fn resolve(client: &HttpClient, url: &str) -> Option<Stream> {
+    let host = Url::parse(url).ok()?.host_str()?.to_owned();
+    let extractor = EXTRACTORS
+        .iter()
+        .find(|extractor| extractor.matches(&host))?;
+
+    match extractor.extract(client, url).await {
+        Ok(stream) => Some(stream),
+        Err(err) => {
+            eprintln!("Couldn't resolve {url}: {err}");
+            None
+        }
+    }
+}
+
+fn absolute(url: &str) -> String {
+    if url.starts_with("//") {
+        format!("https:{url}")
+    } else {
+        url.to_owned()
+    }
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#039;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn not_found(what: &str) -> ScraperError {
+    ScraperError::Parse(format!("{what} not found"))
+}
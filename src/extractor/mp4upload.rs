@@ -0,0 +1,97 @@
+use iced::futures::{FutureExt, future::BoxFuture};
+use regex::Regex;
+
+use crate::{
+    http_client::HttpClient,
+    scraper::{ScraperResult, js_value::JsValue},
+};
+
+use super::{Extractor, Stream, absolute, not_found, packer};
+
+const REFERER: &str = "https://www.mp4upload.com/";
+
+pub struct Mp4UploadExtractor;
+
+impl Extractor for Mp4UploadExtractor {
+    fn matches(&self, host: &str) -> bool {
+        host.ends_with("mp4upload.com")
+    }
+
+    fn extract<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        url: &'a str,
+    ) -> BoxFuture<'a, ScraperResult<Stream>> {
+        async move { parse(&client.text(url).await?) }.boxed()
+    }
+}
+
+fn parse(body: &str) -> ScraperResult<Stream> {
+    let url = match media_url(body) {
+        Some(url) => url,
+        None if packer::is_packed(body) => {
+            media_url(&packer::unpack(body)?).ok_or_else(|| not_found("mp4upload source"))?
+        }
+        None => return Err(not_found("mp4upload source")),
+    };
+
+    Ok(Stream {
+        url: absolute(&url),
+        headers: vec![("Referer".into(), REFERER.into())],
+    })
+}
+
+fn media_url(source: &str) -> Option<String> {
+    if let Ok(src) = JsValue::find(source, "src")
+        && let Some(src) = src.as_str()
+        && is_media(src)
+    {
+        return Some(src.to_owned());
+    }
+
+    Regex::new(r#"(?:https?:)?//[^"'\s]+\.(?:mp4|m3u8)[^"'\s]*"#)
+        .ok()?
+        .find(source)
+        .map(|url| url.as_str().to_owned())
+}
+
+fn is_media(url: &str) -> bool {
+    url.contains(".mp4") || url.contains(".m3u8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_player_source() {
+        let stream = parse(include_str!(
+            "../../tests/fixtures/extractor/mp4upload.html"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            stream.url,
+            "https://a4.mp4upload.com:183/d/xkx3nuw3z3b4quuo4tsdk3rgn3bxkpl2vj4ozdlmdjxbq6sm2u5fz6sy/video.mp4"
+        );
+        assert_eq!(stream.headers, vec![("Referer".into(), REFERER.into())]);
+    }
+
+    #[test]
+    fn extracts_packed_player_source() {
+        let stream = parse(include_str!(
+            "../../tests/fixtures/extractor/mp4upload_packed.html"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            stream.url,
+            "https://s3.mp4upload.com:282/d/qsx2dgsp3b4quuo4v3pvsl3l3x3jafl5rb4wpbn5v4jsc7sj6bsa/video.mp4"
+        );
+    }
+
+    #[test]
+    fn fails_without_source() {
+        assert!(parse("<html><body>File was deleted</body></html>").is_err());
+    }
+}
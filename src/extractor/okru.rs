@@ -0,0 +1,97 @@
+use iced::futures::{FutureExt, future::BoxFuture};
+use serde_json::Value;
+
+use crate::{
+    http_client::HttpClient,
+    scraper::{ScraperResult, error::ScraperError},
+};
+
+use super::{Extractor, Stream, absolute, not_found, unescape_html};
+
+const REFERER: &str = "https://ok.ru/";
+const QUALITIES: [&str; 8] = [
+    "ultra", "quad", "full", "hd", "sd", "low", "lowest", "mobile",
+];
+
+pub struct OkRuExtractor;
+
+impl Extractor for OkRuExtractor {
+    fn matches(&self, host: &str) -> bool {
+        host == "ok.ru" || host.ends_with(".ok.ru")
+    }
+
+    fn extract<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        url: &'a str,
+    ) -> BoxFuture<'a, ScraperResult<Stream>> {
+        async move { parse(&client.text(url).await?) }.boxed()
+    }
+}
+
+fn parse(body: &str) -> ScraperResult<Stream> {
+    let start = body
+        .find("data-options=\"")
+        .map(|start| start + "data-options=\"".len())
+        .ok_or_else(|| not_found("ok.ru player options"))?;
+    let end = body[start..]
+        .find('"')
+        .ok_or_else(|| not_found("ok.ru player options end"))?;
+
+    let options: Value = serde_json::from_str(&unescape_html(&body[start..start + end]))
+        .map_err(|err| ScraperError::Parse(err.to_string()))?;
+
+    let metadata = options["flashvars"]["metadata"]
+        .as_str()
+        .ok_or_else(|| not_found("ok.ru metadata"))?;
+    let metadata: Value =
+        serde_json::from_str(metadata).map_err(|err| ScraperError::Parse(err.to_string()))?;
+
+    let videos = metadata["videos"].as_array().map_or(&[][..], Vec::as_slice);
+    let url = QUALITIES
+        .iter()
+        .find_map(|quality| {
+            videos
+                .iter()
+                .find(|video| video["name"].as_str() == Some(quality))?["url"]
+                .as_str()
+        })
+        .or_else(|| metadata["hlsManifestUrl"].as_str())
+        .ok_or_else(|| not_found("ok.ru video"))?;
+
+    Ok(Stream {
+        url: absolute(url),
+        headers: vec![("Referer".into(), REFERER.into())],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_best_quality() {
+        let stream = parse(include_str!("../../tests/fixtures/extractor/okru.html")).unwrap();
+
+        assert_eq!(
+            stream.url,
+            "https://vd457.okcdn.ru/?expires=1729450000000&srcIp=0.0.0.0&type=3&sig=Qb8zq1nL2pE&ct=0&id=6842581241497"
+        );
+        assert_eq!(stream.headers, vec![("Referer".into(), REFERER.into())]);
+    }
+
+    #[test]
+    fn falls_back_to_hls() {
+        let body = r#"<div data-options="{&quot;flashvars&quot;:{&quot;metadata&quot;:&quot;{\&quot;videos\&quot;:[],\&quot;hlsManifestUrl\&quot;:\&quot;https://vd457.okcdn.ru/video.m3u8?id=1\&quot;}&quot;}}"></div>"#;
+
+        assert_eq!(
+            parse(body).unwrap().url,
+            "https://vd457.okcdn.ru/video.m3u8?id=1"
+        );
+    }
+
+    #[test]
+    fn fails_without_player() {
+        assert!(parse("<html><body>Video has been blocked</body></html>").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use regex::Regex;
+
+use crate::scraper::{ScraperResult, error::ScraperError};
+
+pub fn is_packed(source: &str) -> bool {
+    source.contains("eval(function(p,a,c,k,e,")
+}
+
+pub fn unpack(source: &str) -> ScraperResult<String> {
+    let args = Regex::new(r"(?s)\}\('(.*)',\s*(\d+),\s*(\d+),\s*'(.*?)'\.split\('\|'\)")?;
+    let captures = args
+        .captures(source)
+        .ok_or_else(|| ScraperError::Parse("packed script arguments not found".into()))?;
+
+    let payload = captures[1].replace("\\'", "'").replace("\\\\", "\\");
+    let radix = captures[2]
+        .parse::<u32>()
+        .map_err(|err| ScraperError::Parse(err.to_string()))?;
+    let symbols = captures[4].split('|').collect::<Vec<_>>();
+
+    let word = Regex::new(r"\b\w+\b")?;
+
+    Ok(word
+        .replace_all(&payload, |captures: &regex::Captures| {
+            let word = &captures[0];
+            decode(word, radix)
+                .and_then(|index| symbols.get(index))
+                .filter(|symbol| !symbol.is_empty())
+                .map_or_else(|| word.to_owned(), |symbol| (*symbol).to_owned())
+        })
+        .into_owned())
+}
+
+fn decode(word: &str, radix: u32) -> Option<usize> {
+    word.chars().try_fold(0_usize, |value, c| {
+        let digit = match c {
+            '0'..='9' => u32::from(c) - u32::from('0'),
+            'a'..='z' => u32::from(c) - u32::from('a') + 10,
+            'A'..='Z' => u32::from(c) - u32::from('A') + 36,
+            _ => return None,
+        };
+
+        (digit < radix).then_some(value.checked_mul(radix as usize)? + digit as usize)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpacks_dean_edwards_packer() {
+        let packed = r"eval(function(p,a,c,k,e,d){while(c--)if(k[c])p=p.replace(new RegExp('\\b'+c.toString(a)+'\\b','g'),k[c]);return p}('0.1(\'2\')',3,3,'console|log|hola'.split('|'),0,{}))";
+
+        assert!(is_packed(packed));
+        assert_eq!(unpack(packed).unwrap(), "console.log('hola')");
+    }
+
+    #[test]
+    fn decodes_base62_words() {
+        assert_eq!(decode("z", 62), Some(35));
+        assert_eq!(decode("A", 62), Some(36));
+        assert_eq!(decode("10", 62), Some(62));
+        assert_eq!(decode("A", 36), None);
+    }
+}
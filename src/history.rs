@@ -0,0 +1,176 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use dirs::{config_dir, state_dir};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{
+    download::{format_bytes, format_duration},
+    local_time,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Done,
+    Unprocessed(String),
+    Failed(String),
+    Cancelled,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Done => write!(f, "Completada"),
+            Self::Unprocessed(reason) => write!(f, "Completada sin postprocesar: {reason}"),
+            Self::Failed(reason) => write!(f, "Error: {reason}"),
+            Self::Cancelled => write!(f, "Cancelada"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    pub finished: i64,
+    pub anime: String,
+    pub episode: f64,
+    pub duration: u64,
+    pub size: u64,
+    pub host: Option<String>,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {} episodio {} - {} en {}",
+            format_time(self.finished),
+            self.anime,
+            self.episode,
+            format_bytes(self.size),
+            format_duration(std::time::Duration::from_secs(self.duration))
+        )?;
+
+        if let Some(host) = &self.host {
+            write!(f, " ({host})")?;
+        }
+
+        write!(f, " - {}", self.outcome)
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let mut path = state_dir().or_else(config_dir)?;
+    path.push("ani-link");
+    path.push("history.jsonl");
+    Some(path)
+}
+
+pub fn record(entry: &HistoryEntry) -> Result<(), Box<dyn Error>> {
+    let path = path().ok_or("State path not found")?;
+    fs::create_dir_all(path.parent().ok_or("State path has no parent")?)?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+
+    Ok(())
+}
+
+pub fn load() -> Vec<HistoryEntry> {
+    let Some(file) = path().and_then(|path| File::open(path).ok()) else {
+        return vec![];
+    };
+
+    let mut entries = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect::<Vec<HistoryEntry>>();
+
+    entries.reverse();
+    entries
+}
+
+pub fn export(entries: &[HistoryEntry], path: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, to_csv(entries))?;
+
+    Ok(())
+}
+
+fn to_csv(entries: &[HistoryEntry]) -> String {
+    let mut csv =
+        "fecha,anime,episodio,duracion_segundos,tamano_bytes,servidor,resultado\n".to_owned();
+
+    for entry in entries {
+        let fields = [
+            format_time(entry.finished),
+            entry.anime.clone(),
+            entry.episode.to_string(),
+            entry.duration.to_string(),
+            entry.size.to_string(),
+            entry.host.clone().unwrap_or_default(),
+            entry.outcome.to_string(),
+        ];
+
+        csv.push_str(
+            &fields
+                .iter()
+                .map(|field| escape(field))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+
+    csv
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+fn format_time(timestamp: i64) -> String {
+    let Ok(time) = OffsetDateTime::from_unix_timestamp(timestamp) else {
+        return timestamp.to_string();
+    };
+    let time = local_time::at(time);
+
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}",
+        time.year(),
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(escape("Kaguya-sama"), "Kaguya-sama");
+        assert_eq!(escape("Love, Chunibyo"), "\"Love, Chunibyo\"");
+        assert_eq!(escape("Error: \"404\""), "\"Error: \"\"404\"\"\"");
+    }
+}
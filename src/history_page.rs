@@ -0,0 +1,230 @@
+use std::{mem, sync::Arc};
+
+use iced::{
+    Element, Event, Length, Padding, Task,
+    alignment::Horizontal,
+    event::{self, Status},
+    keyboard::{
+        Event::KeyPressed,
+        Key,
+        key::Named::{ArrowDown, ArrowLeft, ArrowUp, Escape},
+    },
+    never,
+    widget::{
+        Column, Id, Scrollable, column, container,
+        operation::snap_to,
+        rich_text,
+        scrollable::{self, Direction, Scrollbar},
+        span, text,
+    },
+};
+
+use crate::{
+    app, catalogue_cache,
+    config::Config,
+    download::{Download, format_bytes},
+    history::{self, HistoryEntry, Outcome},
+    http_client::HttpClient,
+    list_query_state::ListQueryState,
+    main_menu_page::{self, MainMenuPage},
+    notifications::{self, Action},
+    page::{AppUpdate, Page},
+    presets::{square_box, transparent_button_cond},
+};
+
+const HISTORY_SCROLLABLE_ID: &str = "history_scrollable";
+const EXPORT_FILE_NAME: &str = "historial.csv";
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Click(usize),
+    KeyPressed(Key),
+}
+
+pub struct HistoryPage {
+    pub config: Config,
+    pub client: HttpClient,
+    pub anime_list: ListQueryState,
+    pub download: Arc<Download>,
+    pub entries: Vec<HistoryEntry>,
+    pub selected: usize,
+}
+
+impl Page for HistoryPage {
+    fn view(&self) -> iced::Element<'_, app::Message> {
+        let selected = self.selected;
+        let palette = self.config.theme().palette();
+
+        let list: Element<'_, app::Message> = if self.entries.is_empty() {
+            text("No hay descargas en el historial")
+                .width(Length::Fill)
+                .align_x(Horizontal::Center)
+                .into()
+        } else {
+            Column::with_children(self.entries.iter().enumerate().map(|(i, entry)| {
+                Element::new(
+                    transparent_button_cond(&entry.to_string(), || selected == i)
+                        .on_press(app::Message::History(Message::Click(i))),
+                )
+            }))
+            .into()
+        };
+
+        square_box(
+            column![
+                text("Historial")
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center),
+                text(self.summary())
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center),
+                container(
+                    Scrollable::new(list)
+                        .id(Id::new(HISTORY_SCROLLABLE_ID))
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .direction(Direction::Vertical(Scrollbar::new()))
+                )
+                .padding(Padding {
+                    top: 6.0,
+                    right: 6.0,
+                    bottom: 3.0,
+                    left: 6.0
+                }),
+                container(
+                    rich_text![
+                        span("Subir:").color(palette.text),
+                        span(" ↑ K ").color(palette.primary),
+                        span(" Bajar:").color(palette.text),
+                        span(" ↓ J ").color(palette.primary),
+                        span(" Exportar CSV:").color(palette.text),
+                        span(" E ").color(palette.primary),
+                        span(" Salir:").color(palette.text),
+                        span(" ← H Esc Q").color(palette.primary),
+                    ]
+                    .on_link_click(never)
+                )
+                .align_x(Horizontal::Center)
+                .width(Length::Fill),
+            ]
+            .spacing(3)
+            .padding(3),
+        )
+        .into()
+    }
+
+    fn update(&mut self, message: app::Message) -> AppUpdate {
+        let app::Message::History(message) = message else {
+            return AppUpdate::None;
+        };
+
+        match message {
+            Message::Click(index) => {
+                self.selected = index;
+                AppUpdate::None
+            }
+            Message::KeyPressed(key) => match key.as_ref() {
+                Key::Character("j") | Key::Named(ArrowDown) => {
+                    if self.selected + 1 < self.entries.len() {
+                        self.selected += 1;
+                        return AppUpdate::Task(self.scroll_to_index());
+                    }
+                    AppUpdate::None
+                }
+                Key::Character("k") | Key::Named(ArrowUp) => {
+                    if self.selected > 0 {
+                        self.selected -= 1;
+                        return AppUpdate::Task(self.scroll_to_index());
+                    }
+                    AppUpdate::None
+                }
+                Key::Character("e") => {
+                    self.export();
+                    AppUpdate::None
+                }
+                Key::Character("q" | "h") | Key::Named(ArrowLeft | Escape) => {
+                    AppUpdate::Page(Box::new(MainMenuPage {
+                        catalogue_updated: catalogue_cache::updated(self.config.scraper),
+                        config: mem::take(&mut self.config),
+                        client: mem::take(&mut self.client),
+                        selection: main_menu_page::Selection::History,
+                        anime_list: mem::take(&mut self.anime_list),
+                        download: self.download.clone(),
+                        waiting: false,
+                    }))
+                }
+                _ => AppUpdate::None,
+            },
+        }
+    }
+
+    fn subscription(&self) -> iced::Subscription<app::Message> {
+        event::listen_with(move |event, status, _| match (event, status) {
+            (Event::Keyboard(KeyPressed { key, .. }), Status::Ignored) => {
+                Some(app::Message::History(Message::KeyPressed(key)))
+            }
+            _ => None,
+        })
+    }
+
+    fn theme(&self) -> iced::Theme {
+        self.config.theme()
+    }
+}
+
+impl HistoryPage {
+    fn summary(&self) -> String {
+        let done = self
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.outcome, Outcome::Done | Outcome::Unprocessed(_)))
+            .collect::<Vec<_>>();
+
+        format!(
+            "{} descargas - {} completadas ({}) - {} con error",
+            self.entries.len(),
+            done.len(),
+            format_bytes(done.iter().map(|entry| entry.size).sum()),
+            self.entries
+                .iter()
+                .filter(|entry| matches!(entry.outcome, Outcome::Failed(_)))
+                .count()
+        )
+    }
+
+    fn export(&self) {
+        let path = self.config.download_dir.join(EXPORT_FILE_NAME);
+
+        match history::export(&self.entries, &path) {
+            Ok(()) => notifications::notify(
+                notifications::HISTORY,
+                &format!("Historial exportado a {}", path.display()),
+                vec![Action::OpenFolder(self.config.download_dir.clone())],
+            ),
+            Err(err) => notifications::notify(
+                notifications::HISTORY,
+                &format!("No se ha podido exportar el historial: {err}"),
+                vec![],
+            ),
+        }
+    }
+
+    fn scroll_to_index(&self) -> Task<app::Message> {
+        let list_len = self.entries.len();
+
+        if self.selected >= list_len {
+            return Task::none();
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let offset = self.selected as f32 / list_len as f32;
+
+        snap_to(
+            Id::new(HISTORY_SCROLLABLE_ID),
+            scrollable::RelativeOffset {
+                x: 0.0,
+                y: offset.clamp(0.0, 1.0),
+            },
+        )
+    }
+}
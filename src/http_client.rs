@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use bytes::Bytes;
+use reqwest::{
+    Client, Response, Url,
+    header::{HeaderMap, RETRY_AFTER, SET_COOKIE},
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::timeout,
+};
+
+use crate::scraper::{ScraperResult, error::ScraperError};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HttpConfig {
+    pub max_connections_per_host: usize,
+    pub min_interval_ms: u64,
+    pub timeout_ms: u64,
+    pub max_retries: u32,
+    pub base_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_host: 8,
+            min_interval_ms: 0,
+            timeout_ms: 10_000,
+            max_retries: 6,
+            base_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+struct Host {
+    connections: Semaphore,
+    next_slot: Mutex<Instant>,
+}
+
+#[derive(Default)]
+struct Inner {
+    client: Client,
+    config: HttpConfig,
+    hosts: Mutex<HashMap<String, Arc<Host>>>,
+}
+
+#[derive(Clone, Default)]
+pub struct HttpClient(Arc<Inner>);
+
+impl HttpClient {
+    pub fn new(client: Client, config: HttpConfig) -> Self {
+        Self(Arc::new(Inner {
+            client,
+            config,
+            hosts: Mutex::default(),
+        }))
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.0.client
+    }
+
+    pub async fn text(&self, url: &str) -> ScraperResult<String> {
+        self.execute(url, &[], |_, body| text(&body)).await
+    }
+
+    pub async fn bytes(&self, url: &str) -> ScraperResult<Bytes> {
+        self.execute(url, &[], |_, body| body).await
+    }
+
+    pub async fn text_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> ScraperResult<String> {
+        self.execute(url, headers, |_, body| text(&body)).await
+    }
+
+    pub async fn bytes_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> ScraperResult<Bytes> {
+        self.execute(url, headers, |_, body| body).await
+    }
+
+    pub async fn text_with_cookies(&self, url: &str) -> ScraperResult<(String, Vec<String>)> {
+        self.execute(url, &[], |headers, body| {
+            let cookies = headers
+                .get_all(SET_COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok()?.split(';').next())
+                .map(ToOwned::to_owned)
+                .collect();
+
+            (text(&body), cookies)
+        })
+        .await
+    }
+
+    pub async fn response(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> ScraperResult<Response> {
+        let host = self.host(url)?;
+        let mut attempt = 0;
+
+        loop {
+            let permit = self.permit(&host).await?;
+            let result = self.send(url, headers).await;
+            drop(permit);
+
+            match result {
+                Ok(response) => return Ok(response),
+                Err((err, retry_after)) => self.retry(&mut attempt, err, retry_after).await?,
+            }
+        }
+    }
+
+    async fn execute<T, F>(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        read: F,
+    ) -> ScraperResult<T>
+    where
+        T: Send,
+        F: Fn(&HeaderMap, Bytes) -> T + Send + Sync,
+    {
+        let host = self.host(url)?;
+        let limit = Duration::from_millis(self.0.config.timeout_ms);
+        let mut attempt = 0;
+
+        loop {
+            let permit = self.permit(&host).await?;
+
+            let result = async {
+                let response = self.send(url, headers).await?;
+
+                if let Some(err) = ScraperError::from_status(response.status()) {
+                    return Err((err, None));
+                }
+
+                let headers = response.headers().clone();
+                let body = read_body(response, limit)
+                    .await
+                    .map_err(|err| (err, None))?;
+
+                Ok(read(&headers, body))
+            }
+            .await;
+
+            drop(permit);
+
+            match result {
+                Ok(value) => return Ok(value),
+                Err((err, retry_after)) => self.retry(&mut attempt, err, retry_after).await?,
+            }
+        }
+    }
+
+    async fn permit<'a>(&self, host: &'a Host) -> ScraperResult<SemaphorePermit<'a>> {
+        let permit = host
+            .connections
+            .acquire()
+            .await
+            .map_err(|err| ScraperError::Network(err.to_string()))?;
+
+        let wait = {
+            let now = Instant::now();
+            let mut next_slot = host.next_slot.lock().expect("Couldn't lock mutex");
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + Duration::from_millis(self.0.config.min_interval_ms);
+            drop(next_slot);
+            slot - now
+        };
+        tokio::time::sleep(wait).await;
+
+        Ok(permit)
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<Response, (ScraperError, Option<Duration>)> {
+        let limit = Duration::from_millis(self.0.config.timeout_ms);
+        let request = headers
+            .iter()
+            .fold(self.0.client.get(url), |request, (name, value)| {
+                request.header(name, value)
+            });
+        let response = timeout(limit, request.send())
+            .await
+            .map_err(|_| (ScraperError::Timeout, None))?
+            .map_err(|err| (err.into(), None))?;
+
+        if let Some(err) = ScraperError::from_status(response.status())
+            && err.is_retryable()
+        {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| retry_after(value, SystemTime::now()))
+                .map(|delay| delay.min(Duration::from_millis(self.0.config.max_backoff_ms)));
+            return Err((err, retry_after));
+        }
+
+        Ok(response)
+    }
+
+    async fn retry(
+        &self,
+        attempt: &mut u32,
+        err: ScraperError,
+        retry_after: Option<Duration>,
+    ) -> ScraperResult<()> {
+        if !err.is_retryable() || *attempt >= self.0.config.max_retries {
+            return Err(err);
+        }
+
+        tokio::time::sleep(retry_after.unwrap_or_else(|| self.backoff(*attempt))).await;
+        *attempt += 1;
+
+        Ok(())
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let config = &self.0.config;
+        let ceiling = config
+            .base_backoff_ms
+            .saturating_mul(1 << attempt.min(16))
+            .min(config.max_backoff_ms);
+        let half = ceiling / 2;
+
+        Duration::from_millis(half + fastrand::u64(0..=half))
+    }
+
+    fn host(&self, url: &str) -> ScraperResult<Arc<Host>> {
+        let name = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(ToOwned::to_owned))
+            .ok_or_else(|| ScraperError::Parse(format!("invalid url {url}")))?;
+
+        let mut hosts = self.0.hosts.lock().expect("Couldn't lock mutex");
+
+        Ok(hosts
+            .entry(name)
+            .or_insert_with(|| {
+                Arc::new(Host {
+                    connections: Semaphore::new(self.0.config.max_connections_per_host.max(1)),
+                    next_slot: Mutex::new(Instant::now()),
+                })
+            })
+            .clone())
+    }
+}
+
+async fn read_body(mut response: Response, stall: Duration) -> ScraperResult<Bytes> {
+    let mut body = Vec::new();
+
+    while let Some(chunk) = timeout(stall, response.chunk())
+        .await
+        .map_err(|_| ScraperError::Timeout)??
+    {
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body.into())
+}
+
+fn retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+
+    value
+        .parse::<u64>()
+        .map(Duration::from_secs)
+        .ok()
+        .or_else(|| {
+            httpdate::parse_http_date(value)
+                .ok()
+                .map(|date| date.duration_since(now).unwrap_or_default())
+        })
+}
+
+fn text(body: &[u8]) -> String {
+    String::from_utf8_lossy(body).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_retry_after_forms() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+
+        assert_eq!(retry_after("45", now), Some(Duration::from_secs(45)));
+        assert_eq!(
+            retry_after("Wed, 21 Oct 2015 07:29:30 GMT", now),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after("soon", now), None);
+    }
+}
@@ -4,10 +4,11 @@ use std::sync::{
 };
 
 use bytes::Bytes;
-
 use iced::widget::image::Handle;
-use reqwest::Client;
 use tokio::task::JoinHandle;
+
+use crate::http_client::HttpClient;
+
 pub enum ImageQueryState {
     Obtaining(JoinHandle<Bytes>, Arc<AtomicBool>),
     Obtained(Handle),
@@ -20,21 +21,14 @@ impl Default for ImageQueryState {
 }
 
 impl ImageQueryState {
-    pub fn spawn(client: Client, image_url: String) -> Self {
+    pub fn spawn(client: HttpClient, image_url: String) -> Self {
         let done = Arc::new(AtomicBool::new(false));
         let done2 = done.clone();
 
         Self::Obtaining(
             tokio::spawn(async move {
                 let done = done2;
-                let image = client
-                    .get(image_url)
-                    .send()
-                    .await
-                    .expect("Error sending request for image")
-                    .bytes()
-                    .await
-                    .expect("Error converting image data to bytes");
+                let image = client.bytes(&image_url).await.unwrap_or_default();
 
                 done.store(true, Ordering::Relaxed);
 
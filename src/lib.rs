@@ -1,13 +1,32 @@
 pub mod app;
+mod atomic_file;
+mod batch_prompt;
+mod catalogue_cache;
 mod config;
 mod download;
+mod download_queue;
+mod downloader;
+mod downloads_page;
 mod episodes_page;
+mod extractor;
+mod history;
+mod history_page;
+mod http_client;
 mod image_query_state;
+mod library;
 mod list_query_state;
+mod local_time;
 mod main_menu_page;
+mod media_server;
+mod mirror_picker;
+mod notifications;
 mod options_page;
 mod page;
+mod postprocess;
 mod presets;
+mod schedule;
 mod scraper;
 mod search_page;
+mod storage;
+mod storage_page;
 mod themes;
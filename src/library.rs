@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config,
+    download::{self, DownloadToken},
+    history, media_server,
+    scraper::{anime::Anime, episode::Episode, mirror::MirrorPreference},
+    storage,
+};
+
+const VIDEO_EXTENSIONS: [&str; 7] = ["mp4", "mkv", "webm", "ts", "avi", "m4v", "mov"];
+
+#[derive(Default)]
+pub struct Library {
+    files: HashMap<(String, String), HashMap<String, PathBuf>>,
+    watched: HashMap<PathBuf, i64>,
+}
+
+impl Library {
+    pub fn load() -> Self {
+        Self {
+            files: HashMap::new(),
+            watched: storage::load_watched(),
+        }
+    }
+
+    pub fn scan(&mut self, config: &Config, anime: &Anime, episodes: &[Episode]) {
+        let mut listings = HashMap::new();
+        let local = self
+            .files
+            .entry((config.scraper.name().to_owned(), anime.slug.clone()))
+            .or_default();
+
+        for episode in episodes {
+            let token = DownloadToken {
+                anime: anime.clone(),
+                episode: episode.clone(),
+                special_index: media_server::special_index(episodes, episode),
+                preference: MirrorPreference::default(),
+                post_profile: None,
+            };
+
+            let file = [config.audio, config.audio.next()]
+                .into_iter()
+                .flat_map(|audio| {
+                    [config.media_server_layout, !config.media_server_layout]
+                        .map(|layout| download::stem(config, &token, audio, layout))
+                })
+                .find_map(|stem| find(&mut listings, &stem));
+
+            match file {
+                Some(file) => local.insert(episode.to_string(), file),
+                None => local.remove(&episode.to_string()),
+            };
+        }
+    }
+
+    pub fn get(&self, config: &Config, slug: &str, episode: &Episode) -> Option<&PathBuf> {
+        self.files
+            .get(&(config.scraper.name().to_owned(), slug.to_owned()))?
+            .get(&episode.to_string())
+    }
+
+    pub fn average_size(&self, config: &Config, slug: &str) -> Option<u64> {
+        let sizes = |files: &HashMap<String, PathBuf>| {
+            files
+                .values()
+                .filter_map(|file| fs::metadata(file).ok())
+                .map(|metadata| metadata.len())
+                .collect::<Vec<_>>()
+        };
+
+        let mut found = self
+            .files
+            .get(&(config.scraper.name().to_owned(), slug.to_owned()))
+            .map(sizes)
+            .unwrap_or_default();
+        if found.is_empty() {
+            found = self.files.values().flat_map(sizes).collect();
+        }
+
+        (!found.is_empty()).then(|| found.iter().sum::<u64>() / found.len() as u64)
+    }
+
+    pub const fn watched(&self) -> &HashMap<PathBuf, i64> {
+        &self.watched
+    }
+
+    pub fn is_watched(&self, file: &Path) -> bool {
+        self.watched.contains_key(file)
+    }
+
+    pub fn set_watched(&mut self, file: &Path, watched: bool) {
+        let changed = if watched {
+            self.watched
+                .insert(file.to_owned(), history::now())
+                .is_none()
+        } else {
+            self.watched.remove(file).is_some()
+        };
+
+        if changed {
+            self.save_watched();
+        }
+    }
+
+    pub fn forget(&mut self, path: &Path) {
+        for files in self.files.values_mut() {
+            files.retain(|_, file| !file.starts_with(path));
+        }
+
+        let watched = self.watched.len();
+        self.watched.retain(|file, _| !file.starts_with(path));
+        if self.watched.len() != watched {
+            self.save_watched();
+        }
+    }
+
+    fn save_watched(&self) {
+        if let Err(err) = storage::save_watched(&self.watched) {
+            eprintln!("Couldn't save the watched episodes: {err}");
+        }
+    }
+}
+
+pub fn find_video(stem: &Path) -> Option<PathBuf> {
+    find(&mut HashMap::new(), stem)
+}
+
+fn find(listings: &mut HashMap<PathBuf, Vec<String>>, stem: &Path) -> Option<PathBuf> {
+    let parent = stem.parent()?;
+    let files = listings.entry(parent.to_owned()).or_insert_with(|| {
+        fs::read_dir(parent)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect()
+    });
+
+    files
+        .iter()
+        .find(|file| {
+            download::stem_extension(stem, file).is_some_and(|extension| {
+                VIDEO_EXTENSIONS
+                    .iter()
+                    .any(|video| extension.eq_ignore_ascii_case(video))
+            })
+        })
+        .map(|file| parent.join(file))
+}
@@ -1,45 +1,139 @@
-use std::sync::{Arc, atomic::AtomicUsize};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
-use reqwest::Client;
 use tokio::{runtime::Handle, task::JoinHandle};
 
-use crate::scraper::{ScraperImpl, anime::Anime};
+use crate::{
+    catalogue_cache,
+    http_client::HttpClient,
+    scraper::{ScraperImpl, ScraperResult, SearchProgress, anime::Anime, error::ScraperError},
+};
 
 pub enum ListQueryState {
-    Obtaining(JoinHandle<Vec<Anime>>, Arc<AtomicUsize>),
-    Obtained(Vec<Anime>, Arc<AtomicUsize>),
+    Obtaining(JoinHandle<ScraperResult<Vec<Anime>>>, Arc<SearchProgress>),
+    Refreshing(
+        Vec<Anime>,
+        JoinHandle<ScraperResult<Vec<Anime>>>,
+        Arc<SearchProgress>,
+    ),
+    Obtained(Vec<Anime>, Arc<SearchProgress>),
+    Failed(ScraperError, Arc<SearchProgress>),
 }
 
 impl Default for ListQueryState {
     fn default() -> Self {
-        Self::Obtained(vec![], Arc::new(AtomicUsize::new(0)))
+        Self::Obtained(vec![], Arc::default())
     }
 }
 
 impl ListQueryState {
-    pub fn spawn(scraper: ScraperImpl, client: Client) -> Self {
-        let progress = Arc::new(AtomicUsize::new(0));
+    pub fn spawn(scraper: ScraperImpl, client: HttpClient, ttl: Duration) -> Self {
+        match catalogue_cache::load(scraper) {
+            Some((anime_list, updated))
+                if SystemTime::now()
+                    .duration_since(updated)
+                    .is_ok_and(|age| age < ttl) =>
+            {
+                Self::Obtained(anime_list, Arc::default())
+            }
+            Some((anime_list, _)) => {
+                Self::refresh(Self::Obtained(anime_list, Arc::default()), scraper, client)
+            }
+            None => Self::refresh(Self::default(), scraper, client),
+        }
+    }
+
+    pub fn refresh(self, scraper: ScraperImpl, client: HttpClient) -> Self {
+        let anime_list = match self {
+            Self::Refreshing(..) | Self::Obtaining(..) => return self,
+            Self::Obtained(anime_list, _) if !anime_list.is_empty() => Some(anime_list),
+            Self::Obtained(..) | Self::Failed(..) => None,
+        };
+
+        let progress = Arc::new(SearchProgress::default());
         let progress2 = progress.clone();
-        Self::Obtaining(
-            tokio::spawn(async move {
-                scraper
-                    .try_search(&client, progress)
-                    .await
-                    .expect("Couldn't retrieve the list of animes")
-            }),
-            progress2,
-        )
+        let handle = tokio::spawn(async move {
+            let anime_list = scraper.try_search(&client, progress).await?;
+            if anime_list.is_empty() {
+                return Err(ScraperError::NotFound);
+            }
+
+            if let Err(err) = catalogue_cache::save(scraper, &anime_list) {
+                eprintln!("Couldn't save the catalogue cache: {err}");
+            }
+
+            Ok(anime_list)
+        });
+
+        match anime_list {
+            Some(anime_list) => Self::Refreshing(anime_list, handle, progress2),
+            None => Self::Obtaining(handle, progress2),
+        }
+    }
+
+    pub fn progress(&self) -> Arc<SearchProgress> {
+        match self {
+            Self::Obtaining(_, progress)
+            | Self::Refreshing(_, _, progress)
+            | Self::Obtained(_, progress)
+            | Self::Failed(_, progress) => progress.clone(),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        match self {
+            Self::Obtaining(handle, _) => handle.is_finished(),
+            Self::Refreshing(..) | Self::Obtained(..) | Self::Failed(..) => true,
+        }
+    }
+
+    pub const fn is_refreshing(&self) -> bool {
+        matches!(self, Self::Refreshing(..))
     }
 
     pub fn get(self) -> Self {
         match self {
-            Self::Obtaining(handle, progress) => Self::Obtained(
-                Handle::current()
+            Self::Obtaining(handle, progress) => match Handle::current()
+                .block_on(handle)
+                .expect("Thread couldn't be joined")
+            {
+                Ok(anime_list) => Self::Obtained(anime_list, progress),
+                Err(err) => Self::Failed(err, progress),
+            },
+            Self::Refreshing(anime_list, handle, progress) if handle.is_finished() => {
+                match Handle::current()
                     .block_on(handle)
-                    .expect("Thread couldn't be joined"),
-                progress,
-            ),
-            Self::Obtained(..) => self,
+                    .expect("Thread couldn't be joined")
+                {
+                    Ok(anime_list) => Self::Obtained(anime_list, progress),
+                    Err(err) => {
+                        eprintln!("Couldn't refresh the catalogue: {err}");
+                        Self::Obtained(anime_list, progress)
+                    }
+                }
+            }
+            Self::Refreshing(..) | Self::Obtained(..) | Self::Failed(..) => self,
+        }
+    }
+
+    pub fn split(self) -> (Option<Vec<Anime>>, Self) {
+        match self {
+            Self::Refreshing(anime_list, handle, progress) => {
+                (Some(anime_list), Self::Obtaining(handle, progress))
+            }
+            Self::Obtained(anime_list, _) => (Some(anime_list), Self::default()),
+            Self::Obtaining(..) | Self::Failed(..) => (None, self),
+        }
+    }
+
+    pub fn join(anime_list: Vec<Anime>, refresh: Self) -> Self {
+        match refresh {
+            Self::Obtaining(handle, progress) => Self::Refreshing(anime_list, handle, progress),
+            Self::Refreshing(..) | Self::Obtained(..) | Self::Failed(..) => {
+                Self::Obtained(anime_list, Arc::default())
+            }
         }
     }
 }
@@ -0,0 +1,29 @@
+use time::{OffsetDateTime, UtcOffset};
+
+pub fn now() -> OffsetDateTime {
+    at(OffsetDateTime::now_utc())
+}
+
+pub fn at(time: OffsetDateTime) -> OffsetDateTime {
+    time.to_offset(offset_at(time).unwrap_or(UtcOffset::UTC))
+}
+
+#[cfg(unix)]
+fn offset_at(time: OffsetDateTime) -> Option<UtcOffset> {
+    use std::mem::MaybeUninit;
+
+    let timestamp = libc::time_t::try_from(time.unix_timestamp()).ok()?;
+    let mut tm = MaybeUninit::<libc::tm>::uninit();
+
+    if unsafe { libc::localtime_r(&raw const timestamp, tm.as_mut_ptr()) }.is_null() {
+        return None;
+    }
+    let tm = unsafe { tm.assume_init() };
+
+    UtcOffset::from_whole_seconds(i32::try_from(tm.tm_gmtoff).ok()?).ok()
+}
+
+#[cfg(not(unix))]
+fn offset_at(time: OffsetDateTime) -> Option<UtcOffset> {
+    UtcOffset::local_offset_at(time).ok()
+}
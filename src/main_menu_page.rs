@@ -1,4 +1,4 @@
-use std::{fmt, mem, process::exit, sync::atomic::Ordering};
+use std::{collections::HashSet, fmt, mem, process::exit, sync::Arc, time::SystemTime};
 
 use iced::{
     Event, Font, Length, Subscription,
@@ -13,18 +13,25 @@ use iced::{
     time::{self, Duration},
     widget::{Id, Space, column, container, operation::focus, rich_text, span, text},
 };
-use reqwest::Client;
 use strum_macros::EnumIter;
 
 use crate::{
-    app,
+    app, catalogue_cache,
     config::Config,
+    download::Download,
+    downloads_page::DownloadsPage,
+    history,
+    history_page::HistoryPage,
+    http_client::HttpClient,
     image_query_state::ImageQueryState,
     list_query_state::ListQueryState,
+    notifications,
     options_page::{self, OptionsPage},
     page::{AppUpdate, Page},
     presets::{square_box, transparent_button},
+    scraper::error::ScraperError,
     search_page::{SEARCH_BAR_ID, SearchPage},
+    storage_page::StoragePage,
 };
 
 #[derive(Debug, Clone)]
@@ -36,6 +43,10 @@ pub enum Message {
 #[derive(EnumIter, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Selection {
     Search,
+    Refresh,
+    Downloads,
+    Storage,
+    History,
     Options,
     Exit,
 }
@@ -43,14 +54,22 @@ pub enum Selection {
 impl Selection {
     pub const fn next(self) -> Self {
         match self {
-            Self::Search => Self::Options,
+            Self::Search => Self::Refresh,
+            Self::Refresh => Self::Downloads,
+            Self::Downloads => Self::Storage,
+            Self::Storage => Self::History,
+            Self::History => Self::Options,
             Self::Options | Self::Exit => Self::Exit,
         }
     }
 
     pub const fn prev(self) -> Self {
         match self {
-            Self::Search | Self::Options => Self::Search,
+            Self::Search | Self::Refresh => Self::Search,
+            Self::Downloads => Self::Refresh,
+            Self::Storage => Self::Downloads,
+            Self::History => Self::Storage,
+            Self::Options => Self::History,
             Self::Exit => Self::Options,
         }
     }
@@ -63,6 +82,10 @@ impl fmt::Display for Selection {
             "{}",
             match self {
                 Self::Search => "Buscar",
+                Self::Refresh => "Actualizar catálogo",
+                Self::Downloads => "Descargas",
+                Self::Storage => "Almacenamiento",
+                Self::History => "Historial",
                 Self::Options => "Opciones",
                 Self::Exit => "Salir",
             }
@@ -72,22 +95,23 @@ impl fmt::Display for Selection {
 
 pub struct MainMenuPage {
     pub config: Config,
-    pub client: Client,
+    pub client: HttpClient,
     pub selection: Selection,
     pub anime_list: ListQueryState,
+    pub download: Arc<Download>,
     pub waiting: bool,
+    pub catalogue_updated: Option<SystemTime>,
 }
 
 impl Page for MainMenuPage {
+    #[allow(clippy::too_many_lines)]
     fn view(&self) -> iced::Element<'_, app::Message> {
-        let progress = match &self.anime_list {
-            ListQueryState::Obtaining(_, progress) | ListQueryState::Obtained(_, progress) => {
-                progress.clone()
-            }
-        }
-        .load(Ordering::Relaxed);
-
-        let total = self.config.scraper.pages();
+        let progress = if self.config.scraper.capabilities().progress {
+            let progress = self.anime_list.progress();
+            format!(" ({}/{})", progress.completed(), progress.total())
+        } else {
+            "...".to_owned()
+        };
 
         square_box(column![
             Space::new().height(Length::Fill),
@@ -106,9 +130,18 @@ impl Page for MainMenuPage {
             .align_x(Horizontal::Center)
             .width(Length::Fill),
             container(
-                if self.waiting {
+                if let ListQueryState::Failed(err, _) = &self.anime_list {
                     transparent_button(
-                        &format!("Cargando ({progress}/{total})"),
+                        &if err.is_retryable() {
+                            format!("Reintentar ({err})")
+                        } else {
+                            format!("Error: {err}")
+                        },
+                        matches!(self.selection, Selection::Search),
+                    )
+                } else if self.waiting {
+                    transparent_button(
+                        &format!("Cargando{progress}"),
                         matches!(self.selection, Selection::Search),
                     )
                 } else {
@@ -118,6 +151,62 @@ impl Page for MainMenuPage {
             )
             .align_x(Horizontal::Center)
             .width(Length::Fill),
+            container(
+                if self.anime_list.is_refreshing() {
+                    transparent_button(
+                        &format!("Actualizando catálogo{progress}"),
+                        matches!(self.selection, Selection::Refresh),
+                    )
+                } else if let Some(updated) = self.catalogue_updated {
+                    transparent_button(
+                        &format!(
+                            "Actualizar catálogo ({})",
+                            catalogue_cache::format_age(updated)
+                        ),
+                        matches!(self.selection, Selection::Refresh),
+                    )
+                } else {
+                    transparent_button(
+                        "Actualizar catálogo",
+                        matches!(self.selection, Selection::Refresh),
+                    )
+                }
+                .on_press(app::Message::MainMenu(Message::Select(Selection::Refresh)))
+            )
+            .align_x(Horizontal::Center)
+            .width(Length::Fill),
+            container(
+                match self.download.pending() {
+                    0 => transparent_button(
+                        "Descargas",
+                        matches!(self.selection, Selection::Downloads),
+                    ),
+                    pending => transparent_button(
+                        &format!("Descargas ({pending} pendientes)"),
+                        matches!(self.selection, Selection::Downloads),
+                    ),
+                }
+                .on_press(app::Message::MainMenu(Message::Select(
+                    Selection::Downloads
+                )))
+            )
+            .align_x(Horizontal::Center)
+            .width(Length::Fill),
+            container(
+                transparent_button(
+                    "Almacenamiento",
+                    matches!(self.selection, Selection::Storage)
+                )
+                .on_press(app::Message::MainMenu(Message::Select(Selection::Storage)))
+            )
+            .align_x(Horizontal::Center)
+            .width(Length::Fill),
+            container(
+                transparent_button("Historial", matches!(self.selection, Selection::History))
+                    .on_press(app::Message::MainMenu(Message::Select(Selection::History)))
+            )
+            .align_x(Horizontal::Center)
+            .width(Length::Fill),
             container(
                 transparent_button("Opciones", matches!(self.selection, Selection::Options),)
                     .on_press(app::Message::MainMenu(Message::Select(Selection::Options)))
@@ -159,66 +248,9 @@ impl Page for MainMenuPage {
 
     #[allow(clippy::too_many_lines)]
     fn update(&mut self, message: app::Message) -> AppUpdate {
-        let mut change_selection = |selection| -> AppUpdate {
-            match selection {
-                Selection::Search => {
-                    let progress = match &self.anime_list {
-                        ListQueryState::Obtaining(_, progress)
-                        | ListQueryState::Obtained(_, progress) => progress.clone(),
-                    };
-
-                    if progress.load(Ordering::Relaxed) != self.config.scraper.pages() {
-                        self.waiting = true;
-                        return AppUpdate::None;
-                    }
-
-                    let anime_list = mem::take(&mut self.anime_list);
-
-                    let anime_list = match anime_list {
-                        ListQueryState::Obtaining(..) => anime_list.get(),
-                        ListQueryState::Obtained(..) => anime_list,
-                    };
-
-                    let ListQueryState::Obtained(anime_list, _) = anime_list else {
-                        panic!("Should not happen");
-                    };
-
-                    let filtered_list = anime_list.clone();
-                    let image_query = ImageQueryState::spawn(
-                        self.client.clone(),
-                        anime_list
-                            .first()
-                            .expect("No animes found")
-                            .image_url
-                            .clone(),
-                    );
-
-                    AppUpdate::Both((
-                        Box::new(SearchPage {
-                            config: mem::take(&mut self.config),
-                            client: mem::take(&mut self.client),
-                            anime_list,
-                            query: String::new(),
-                            selected: 0,
-                            filtered_list,
-                            image: image_query,
-                        }),
-                        focus(Id::new(SEARCH_BAR_ID)),
-                    ))
-                }
-                Selection::Options => AppUpdate::Page(Box::new(OptionsPage {
-                    old_config: self.config.clone(),
-                    config: mem::take(&mut self.config),
-                    client: mem::take(&mut self.client),
-                    anime_list: mem::take(&mut self.anime_list),
-                    selection: options_page::Options::Scraper,
-                })),
-                Selection::Exit => exit(0),
-            }
-        };
         if let app::Message::MainMenu(message) = message {
             match message {
-                Message::Select(selection) => change_selection(selection),
+                Message::Select(selection) => self.change_selection(selection),
                 Message::KeyPressed(key) => match key.as_ref() {
                     Key::Character("j") | Key::Named(ArrowDown) => {
                         self.selection = self.selection.next();
@@ -229,53 +261,23 @@ impl Page for MainMenuPage {
                         AppUpdate::None
                     }
                     Key::Character("l") | Key::Named(Enter | ArrowRight) => {
-                        change_selection(self.selection)
+                        self.change_selection(self.selection)
                     }
                     Key::Character("h") | Key::Named(Escape | ArrowLeft) => exit(0),
                     _ => AppUpdate::None,
                 },
             }
         } else if matches!(message, app::Message::Update) {
-            let progress = match &self.anime_list {
-                ListQueryState::Obtaining(_, progress) | ListQueryState::Obtained(_, progress) => {
-                    progress.clone()
+            if self.anime_list.is_refreshing() {
+                self.anime_list = mem::take(&mut self.anime_list).get();
+                if !self.anime_list.is_refreshing() {
+                    self.catalogue_updated = catalogue_cache::updated(self.config.scraper);
                 }
-            };
-
-            if self.waiting && progress.load(Ordering::Relaxed) == self.config.scraper.pages() {
-                let anime_list = mem::take(&mut self.anime_list);
-
-                let anime_list = match anime_list {
-                    ListQueryState::Obtaining(..) => anime_list.get(),
-                    ListQueryState::Obtained(..) => anime_list,
-                };
-
-                let ListQueryState::Obtained(anime_list, _) = anime_list else {
-                    panic!("Should not happen");
-                };
-
-                let filtered_list = anime_list.clone();
-                let image_query = ImageQueryState::spawn(
-                    self.client.clone(),
-                    anime_list
-                        .first()
-                        .expect("No animes found")
-                        .image_url
-                        .clone(),
-                );
+            }
 
-                AppUpdate::Both((
-                    Box::new(SearchPage {
-                        config: mem::take(&mut self.config),
-                        client: mem::take(&mut self.client),
-                        anime_list,
-                        query: String::new(),
-                        selected: 0,
-                        filtered_list,
-                        image: image_query,
-                    }),
-                    focus(Id::new(SEARCH_BAR_ID)),
-                ))
+            if self.waiting && self.anime_list.is_ready() {
+                self.waiting = false;
+                self.open_search()
             } else {
                 AppUpdate::None
             }
@@ -305,3 +307,108 @@ impl Page for MainMenuPage {
         self.config.theme()
     }
 }
+
+impl MainMenuPage {
+    fn change_selection(&mut self, selection: Selection) -> AppUpdate {
+        match selection {
+            Selection::Search => {
+                if let ListQueryState::Failed(err, _) = &self.anime_list {
+                    if !err.is_retryable() {
+                        notifications::notify(
+                            notifications::CATALOGUE,
+                            &format!("No se ha podido obtener la lista de animes: {err}"),
+                            vec![],
+                        );
+                        return AppUpdate::None;
+                    }
+
+                    self.anime_list = mem::take(&mut self.anime_list)
+                        .refresh(self.config.scraper, self.client.clone());
+                }
+
+                if !self.anime_list.is_ready() {
+                    self.waiting = true;
+                    return AppUpdate::None;
+                }
+
+                self.open_search()
+            }
+            Selection::Refresh => {
+                self.anime_list = mem::take(&mut self.anime_list)
+                    .refresh(self.config.scraper, self.client.clone());
+                AppUpdate::None
+            }
+            Selection::Downloads => AppUpdate::Page(Box::new(DownloadsPage {
+                config: mem::take(&mut self.config),
+                client: mem::take(&mut self.client),
+                anime_list: mem::take(&mut self.anime_list),
+                download: self.download.clone(),
+                selected: 0,
+            })),
+            Selection::Storage => {
+                let page = StoragePage {
+                    config: mem::take(&mut self.config),
+                    client: mem::take(&mut self.client),
+                    anime_list: mem::take(&mut self.anime_list),
+                    download: self.download.clone(),
+                    usage: None,
+                    selected: 0,
+                    marked: HashSet::new(),
+                    confirm: false,
+                };
+                let task = page.scan();
+                AppUpdate::Both((Box::new(page), task))
+            }
+            Selection::History => AppUpdate::Page(Box::new(HistoryPage {
+                config: mem::take(&mut self.config),
+                client: mem::take(&mut self.client),
+                anime_list: mem::take(&mut self.anime_list),
+                download: self.download.clone(),
+                entries: history::load(),
+                selected: 0,
+            })),
+            Selection::Options => AppUpdate::Page(Box::new(OptionsPage {
+                download_dir: self.config.download_dir.to_string_lossy().into_owned(),
+                old_config: self.config.clone(),
+                config: mem::take(&mut self.config),
+                client: mem::take(&mut self.client),
+                anime_list: mem::take(&mut self.anime_list),
+                download: self.download.clone(),
+                selection: options_page::Options::Scraper,
+            })),
+            Selection::Exit => exit(0),
+        }
+    }
+
+    fn open_search(&mut self) -> AppUpdate {
+        let (anime_list, refresh) = mem::take(&mut self.anime_list).get().split();
+
+        let Some(anime_list) = anime_list else {
+            self.anime_list = refresh;
+            return AppUpdate::None;
+        };
+
+        let Some(first) = anime_list.first() else {
+            self.anime_list = ListQueryState::Failed(ScraperError::NotFound, Arc::default());
+            return AppUpdate::None;
+        };
+
+        let image_query = ImageQueryState::spawn(self.client.clone(), first.image_url.clone());
+        let filtered_list = anime_list.clone();
+
+        AppUpdate::Both((
+            Box::new(SearchPage {
+                config: mem::take(&mut self.config),
+                client: mem::take(&mut self.client),
+                anime_list,
+                query: String::new(),
+                selected: 0,
+                filtered_list,
+                image: image_query,
+                refresh,
+                download: self.download.clone(),
+            }),
+            focus(Id::new(SEARCH_BAR_ID)),
+        ))
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    error::Error,
+    ffi::OsString,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::ImageFormat;
+
+use crate::{
+    download::{DownloadToken, sanitize},
+    http_client::HttpClient,
+    scraper::{anime::AnimeStatus, episode::Episode, mirror::Audio},
+};
+
+pub fn show_dir(root: &Path, token: &DownloadToken) -> PathBuf {
+    root.join(sanitize(&token.anime.title))
+}
+
+pub fn episode_path(token: &DownloadToken, audio: Audio) -> PathBuf {
+    let show = sanitize(&token.anime.title);
+    let season = season(token);
+    let suffix = match audio {
+        Audio::Sub => "",
+        Audio::Dub => " - DUB",
+    };
+
+    [
+        show.clone(),
+        format!("Season {season:02}"),
+        format!("{show} - S{season:02}E{:02}{suffix}", index(token)),
+    ]
+    .iter()
+    .collect()
+}
+
+pub async fn write_metadata(
+    client: &HttpClient,
+    root: &Path,
+    token: &DownloadToken,
+    stem: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let show_dir = show_dir(root, token);
+    fs::create_dir_all(&show_dir)?;
+
+    fs::write(show_dir.join("tvshow.nfo"), tvshow_nfo(token))?;
+
+    let mut nfo = OsString::from(stem.as_os_str());
+    nfo.push(".nfo");
+    fs::write(PathBuf::from(nfo), episode_nfo(token))?;
+
+    let poster = show_dir.join("poster.jpg");
+    if !poster.exists() && !token.anime.image_url.is_empty() {
+        let bytes = client.bytes(&token.anime.image_url).await?;
+        image::load_from_memory(&bytes)?
+            .to_rgb8()
+            .save_with_format(poster, ImageFormat::Jpeg)?;
+    }
+
+    Ok(())
+}
+
+pub fn special_index(episodes: &[Episode], episode: &Episode) -> Option<u32> {
+    if !is_season_zero(episode) {
+        return None;
+    }
+
+    let mut specials = episodes
+        .iter()
+        .filter(|episode| is_season_zero(episode))
+        .collect::<Vec<_>>();
+    specials.sort_by(|a, b| a.number.total_cmp(&b.number));
+
+    let position = specials.iter().position(|special| *special == episode)?;
+    u32::try_from(position + 1).ok()
+}
+
+pub fn numbered(episodes: Vec<Episode>) -> Vec<(Episode, Option<u32>)> {
+    let indices = episodes
+        .iter()
+        .map(|episode| special_index(&episodes, episode))
+        .collect::<Vec<_>>();
+
+    episodes.into_iter().zip(indices).collect()
+}
+
+fn is_season_zero(episode: &Episode) -> bool {
+    episode.is_special() || episode.number.fract() != 0.0
+}
+
+fn season(token: &DownloadToken) -> u32 {
+    u32::from(!is_season_zero(&token.episode))
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn index(token: &DownloadToken) -> u32 {
+    token
+        .special_index
+        .unwrap_or_else(|| token.episode.number.trunc().max(0.0) as u32)
+}
+
+fn tvshow_nfo(token: &DownloadToken) -> String {
+    let anime = &token.anime;
+    let mut nfo = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+
+    nfo.push_str("<tvshow>\n");
+    element(&mut nfo, "title", &anime.title);
+    if let Some(original) = anime.alt_titles.first() {
+        element(&mut nfo, "originaltitle", original);
+    }
+    element(&mut nfo, "plot", &anime.synopsis);
+    if let Some(year) = anime.year {
+        element(&mut nfo, "year", &year.to_string());
+    }
+    if let Some(status) = anime.status {
+        element(
+            &mut nfo,
+            "status",
+            match status {
+                AnimeStatus::Airing | AnimeStatus::Upcoming => "Continuing",
+                AnimeStatus::Finished => "Ended",
+            },
+        );
+    }
+    for genre in &anime.genres {
+        element(&mut nfo, "genre", genre);
+    }
+    let _ = writeln!(
+        nfo,
+        "  <uniqueid type=\"ani-link\" default=\"true\">{}</uniqueid>",
+        escape(&anime.slug)
+    );
+    nfo.push_str("</tvshow>\n");
+
+    nfo
+}
+
+fn episode_nfo(token: &DownloadToken) -> String {
+    let episode = &token.episode;
+    let mut nfo = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+
+    nfo.push_str("<episodedetails>\n");
+    element(
+        &mut nfo,
+        "title",
+        &episode
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("Episodio {episode}")),
+    );
+    element(&mut nfo, "showtitle", &token.anime.title);
+    element(&mut nfo, "season", &season(token).to_string());
+    element(&mut nfo, "episode", &index(token).to_string());
+    if let Some(air_date) = &episode.air_date {
+        element(&mut nfo, "aired", air_date);
+    }
+    if let Some(thumbnail) = &episode.thumbnail_url {
+        element(&mut nfo, "thumb", thumbnail);
+    }
+    nfo.push_str("</episodedetails>\n");
+
+    nfo
+}
+
+fn element(nfo: &mut String, name: &str, value: &str) {
+    let _ = writeln!(nfo, "  <{name}>{}</{name}>", escape(value));
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        download::fixtures,
+        scraper::{
+            anime::Anime,
+            episode::{Episode, EpisodeKind},
+        },
+    };
+
+    fn token(episode: Episode) -> DownloadToken {
+        let mut token = fixtures::token("Kaguya-sama: Love Is War", episode);
+        token.anime = Anime {
+            alt_titles: vec!["Kaguya-sama wa Kokurasetai".to_owned()],
+            slug: "kaguya-sama".to_owned(),
+            id: "42".to_owned(),
+            synopsis: "Shirogane & Shinomiya <3".to_owned(),
+            genres: vec!["Comedia".to_owned(), "Romance".to_owned()],
+            year: Some(2019),
+            status: Some(AnimeStatus::Finished),
+            episode_count: Some(12),
+            ..token.anime
+        };
+        token
+    }
+
+    #[test]
+    fn names_episodes_by_season() {
+        assert_eq!(
+            episode_path(&token(Episode::new(5.0)), Audio::Sub),
+            PathBuf::from("Kaguya-sama_ Love Is War/Season 01/Kaguya-sama_ Love Is War - S01E05")
+        );
+
+        let special = Episode {
+            kind: EpisodeKind::Ova,
+            ..Episode::new(1.0)
+        };
+        assert_eq!(
+            episode_path(&token(special), Audio::Dub),
+            PathBuf::from(
+                "Kaguya-sama_ Love Is War/Season 00/Kaguya-sama_ Love Is War - S00E01 - DUB"
+            )
+        );
+    }
+
+    #[test]
+    fn sends_fractional_episodes_to_season_zero() {
+        let ova = Episode {
+            kind: EpisodeKind::Ova,
+            ..Episode::new(1.0)
+        };
+        let episodes = [
+            Episode::new(12.0),
+            Episode::new(12.5),
+            ova,
+            Episode::new(13.0),
+        ];
+
+        assert_eq!(special_index(&episodes, &episodes[0]), None);
+        assert_eq!(special_index(&episodes, &episodes[2]), Some(1));
+
+        let fractional = DownloadToken {
+            special_index: special_index(&episodes, &episodes[1]),
+            ..token(Episode::new(12.5))
+        };
+        assert_eq!(
+            episode_path(&fractional, Audio::Sub),
+            PathBuf::from("Kaguya-sama_ Love Is War/Season 00/Kaguya-sama_ Love Is War - S00E02")
+        );
+
+        let nfo = episode_nfo(&fractional);
+        assert!(nfo.contains("<season>0</season>"));
+        assert!(nfo.contains("<episode>2</episode>"));
+    }
+
+    #[test]
+    fn numbers_specials_against_the_full_season() {
+        let ova = Episode {
+            kind: EpisodeKind::Ova,
+            ..Episode::new(1.0)
+        };
+        let downloaded = [ova.clone()];
+
+        let pending = numbered(vec![Episode::new(12.0), ova, Episode::new(12.5)])
+            .into_iter()
+            .filter(|(episode, _)| !downloaded.contains(episode))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            pending,
+            vec![(Episode::new(12.0), None), (Episode::new(12.5), Some(2))]
+        );
+    }
+
+    #[test]
+    fn writes_escaped_show_metadata() {
+        let nfo = tvshow_nfo(&token(Episode::new(1.0)));
+
+        assert!(nfo.contains("<title>Kaguya-sama: Love Is War</title>"));
+        assert!(nfo.contains("<originaltitle>Kaguya-sama wa Kokurasetai</originaltitle>"));
+        assert!(nfo.contains("<plot>Shirogane &amp; Shinomiya &lt;3</plot>"));
+        assert!(nfo.contains("<year>2019</year>"));
+        assert!(nfo.contains("<status>Ended</status>"));
+        assert!(nfo.contains("<genre>Romance</genre>"));
+    }
+
+    #[test]
+    fn writes_episode_metadata() {
+        let episode = Episode {
+            title: Some("Quiero invitarla al cine".to_owned()),
+            air_date: Some("2019-01-12".to_owned()),
+            ..Episode::new(1.0)
+        };
+        let nfo = episode_nfo(&token(episode));
+
+        assert!(nfo.contains("<title>Quiero invitarla al cine</title>"));
+        assert!(nfo.contains("<season>1</season>"));
+        assert!(nfo.contains("<episode>1</episode>"));
+        assert!(nfo.contains("<aired>2019-01-12</aired>"));
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::{Duration, Instant};
+
+use iced::{
+    Element, Length, Task,
+    alignment::Horizontal,
+    never,
+    theme::Palette,
+    widget::{Column, Space, column, container, rich_text, row, span, text},
+};
+
+use crate::{
+    app, episodes_page,
+    http_client::HttpClient,
+    presets::{square_box, transparent_button_cond},
+    scraper::mirror::{Mirror, MirrorPreference},
+};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Probe {
+    Pending,
+    Reachable(Duration),
+    Unreachable,
+}
+
+pub struct MirrorPicker {
+    pub title: String,
+    pub entries: Vec<(Mirror, Probe)>,
+    pub selected: usize,
+}
+
+impl MirrorPicker {
+    pub fn new(title: String, mirrors: Vec<Mirror>, preference: &MirrorPreference) -> Self {
+        let mut picker = Self {
+            title,
+            entries: mirrors
+                .into_iter()
+                .map(|mirror| (mirror, Probe::Pending))
+                .collect(),
+            selected: 0,
+        };
+        picker.sort(preference);
+        picker
+    }
+
+    pub fn sort(&mut self, preference: &MirrorPreference) {
+        let selected = self.selected().map(|mirror| mirror.url.clone());
+
+        self.entries
+            .sort_by_key(|(mirror, _)| preference.sort_key(mirror));
+
+        self.selected = selected
+            .and_then(|url| {
+                self.entries
+                    .iter()
+                    .position(|(mirror, _)| mirror.url == url)
+            })
+            .unwrap_or_default();
+    }
+
+    pub fn selected(&self) -> Option<&Mirror> {
+        self.entries.get(self.selected).map(|(mirror, _)| mirror)
+    }
+
+    pub const fn next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub const fn prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn set_probe(&mut self, url: &str, latency: Option<Duration>) {
+        if let Some((_, probe)) = self
+            .entries
+            .iter_mut()
+            .find(|(mirror, _)| mirror.url == url)
+        {
+            *probe = latency.map_or(Probe::Unreachable, Probe::Reachable);
+        }
+    }
+
+    pub fn probe_all(&self, client: &HttpClient) -> Task<app::Message> {
+        Task::batch(self.entries.iter().map(|(mirror, _)| {
+            let client = client.clone();
+            let url = mirror.url.clone();
+
+            Task::perform(
+                async move {
+                    let start = Instant::now();
+                    let reachable = client
+                        .client()
+                        .get(&url)
+                        .timeout(PROBE_TIMEOUT)
+                        .send()
+                        .await
+                        .is_ok_and(|response| response.status().is_success());
+
+                    (url, reachable.then(|| start.elapsed()))
+                },
+                |(url, latency)| {
+                    app::Message::Episodes(episodes_page::Message::Pinged(url, latency))
+                },
+            )
+        }))
+    }
+
+    pub fn view(
+        &self,
+        preference: &MirrorPreference,
+        palette: Palette,
+    ) -> Element<'_, app::Message> {
+        let selected = self.selected;
+
+        let entries =
+            Column::with_children(self.entries.iter().enumerate().map(|(i, (mirror, probe))| {
+                let host = mirror.host().unwrap_or_else(|| mirror.url.clone());
+                let status = match probe {
+                    Probe::Pending => "comprobando...".to_owned(),
+                    Probe::Reachable(latency) => format!("{} ms", latency.as_millis()),
+                    Probe::Unreachable => "sin respuesta".to_owned(),
+                };
+                let mut flags = vec![];
+                if preference.is_pinned(mirror) {
+                    flags.push("fijado");
+                }
+                if preference.host_rank(mirror).is_none() {
+                    flags.push("no preferido");
+                }
+                let flags = if flags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", flags.join(", "))
+                };
+
+                Element::new(
+                    transparent_button_cond(
+                        &format!("{host:<24} {:<4} {status:<16}{flags}", mirror.audio),
+                        || selected == i,
+                    )
+                    .on_press(app::Message::Episodes(
+                        episodes_page::Message::PickerClick(i),
+                    )),
+                )
+            }));
+
+        row![
+            Space::new().width(Length::FillPortion(1)),
+            column![
+                Space::new().height(Length::FillPortion(1)),
+                square_box(
+                    column![
+                        text(&self.title)
+                            .width(Length::Fill)
+                            .align_x(Horizontal::Center),
+                        entries,
+                        container(
+                            rich_text![
+                                span("Reproducir:").color(palette.text),
+                                span(" Enter L ").color(palette.primary),
+                                span(" Syncplay:").color(palette.text),
+                                span(" S ").color(palette.primary),
+                                span(" Fijar servidor:").color(palette.text),
+                                span(" P ").color(palette.primary),
+                                span(" Prioridad:").color(palette.text),
+                                span(" Mayús+K/J ").color(palette.primary),
+                                span(" Cerrar:").color(palette.text),
+                                span(" Esc Q").color(palette.primary),
+                            ]
+                            .on_link_click(never)
+                        )
+                        .align_x(Horizontal::Center)
+                        .width(Length::Fill),
+                    ]
+                    .padding(10)
+                    .spacing(3)
+                )
+                .style(move |theme| {
+                    let mut background = theme.palette().background;
+                    background.a = 1.0;
+                    container::Style {
+                        background: Some(iced::Background::Color(background)),
+                        ..Default::default()
+                    }
+                })
+                .width(Length::Fill)
+                .height(Length::Shrink),
+                Space::new().height(Length::FillPortion(1)),
+            ]
+            .width(Length::FillPortion(3)),
+            Space::new().width(Length::FillPortion(1)),
+        ]
+        .into()
+    }
+}
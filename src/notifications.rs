@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    path::PathBuf,
+    sync::{
+        LazyLock, Mutex,
+        mpsc::{self, Sender},
+    },
+    thread,
+};
+
+use iced::{
+    Subscription,
+    futures::{SinkExt, Stream},
+    stream,
+};
+use notify_rust::Notification;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::app;
+
+pub const DOWNLOADS: &str = "downloads";
+pub const STORAGE: &str = "storage";
+pub const HISTORY: &str = "history";
+pub const CATALOGUE: &str = "catalogue";
+pub const PLAYER: &str = "player";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Play(PathBuf),
+    Retry(Vec<u64>),
+    OpenFolder(PathBuf),
+}
+
+impl Action {
+    const fn label(&self) -> &'static str {
+        match self {
+            Self::Play(_) => "Reproducir",
+            Self::Retry(_) => "Reintentar",
+            Self::OpenFolder(_) => "Abrir carpeta",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Group {
+    id: Option<u32>,
+    actions: Vec<Action>,
+    waiting: bool,
+    failed: Vec<u64>,
+}
+
+struct Channel {
+    tx: UnboundedSender<Action>,
+    rx: Mutex<Option<UnboundedReceiver<Action>>>,
+}
+
+static CHANNEL: LazyLock<Channel> = LazyLock::new(|| {
+    let (tx, rx) = unbounded_channel();
+    Channel {
+        tx,
+        rx: Mutex::new(Some(rx)),
+    }
+});
+static GROUPS: LazyLock<Mutex<HashMap<String, Group>>> = LazyLock::new(Mutex::default);
+static WORKER: LazyLock<Sender<(String, String, Vec<Action>)>> = LazyLock::new(|| {
+    let (tx, rx) = mpsc::channel::<(String, String, Vec<Action>)>();
+    thread::spawn(move || {
+        for (group, body, actions) in rx {
+            show(&group, &body, actions);
+        }
+    });
+    tx
+});
+
+pub fn subscription() -> Subscription<app::Message> {
+    Subscription::run(listen)
+}
+
+fn listen() -> impl Stream<Item = app::Message> {
+    stream::channel(16, async |mut output| {
+        let Some(mut rx) = CHANNEL.rx.lock().expect("Couldn't lock mutex").take() else {
+            return;
+        };
+
+        while let Some(action) = rx.recv().await {
+            let _ = output.send(app::Message::Notification(action)).await;
+        }
+    })
+}
+
+pub fn notify(group: &str, body: &str, actions: Vec<Action>) {
+    let _ = WORKER.send((group.to_owned(), body.to_owned(), actions));
+}
+
+fn show(group: &str, body: &str, mut actions: Vec<Action>) {
+    let mut groups = GROUPS.lock().expect("Couldn't lock mutex");
+    let entry = groups.entry(group.to_owned()).or_default();
+
+    let mut body = body.to_owned();
+    if !entry.failed.is_empty()
+        && !actions
+            .iter()
+            .any(|action| matches!(action, Action::Retry(_)))
+    {
+        if entry.failed.len() > 1 {
+            let _ = write!(body, "\n{} descargas con error", entry.failed.len());
+        }
+        actions.push(Action::Retry(entry.failed.clone()));
+    }
+    let id = entry.id;
+    drop(groups);
+
+    let mut notification = Notification::new();
+    notification.summary("Ani-link").body(&body);
+    for (i, action) in actions.iter().enumerate() {
+        notification.action(&i.to_string(), action.label());
+    }
+    if let Some(id) = id {
+        notification.id(id);
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let Ok(handle) = notification.show() else {
+            return;
+        };
+
+        let mut groups = GROUPS.lock().expect("Couldn't lock mutex");
+        let entry = groups.entry(group.to_owned()).or_default();
+        entry.id = Some(handle.id());
+        entry.actions = actions;
+
+        if !entry.waiting && !entry.actions.is_empty() {
+            entry.waiting = true;
+            let group = group.to_owned();
+            thread::spawn(move || wait_for_action(handle, &group));
+        }
+        drop(groups);
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        let _ = notification.show().is_ok();
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn wait_for_action(handle: notify_rust::NotificationHandle, group: &str) {
+    let mut closed = false;
+
+    handle.wait_for_action(|identifier| {
+        if identifier == "__closed" {
+            closed = true;
+            return;
+        }
+
+        let action = GROUPS
+            .lock()
+            .expect("Couldn't lock mutex")
+            .get(group)
+            .and_then(|entry| {
+                entry
+                    .actions
+                    .get(identifier.parse::<usize>().ok()?)
+                    .cloned()
+            });
+
+        if let Some(action) = action {
+            let _ = CHANNEL.tx.send(action);
+        }
+    });
+
+    if let Some(entry) = GROUPS.lock().expect("Couldn't lock mutex").get_mut(group) {
+        entry.waiting = false;
+        if closed {
+            entry.id = None;
+        }
+    }
+}
+
+pub fn failed(group: &str, id: u64, body: &str) {
+    GROUPS
+        .lock()
+        .expect("Couldn't lock mutex")
+        .entry(group.to_owned())
+        .or_default()
+        .failed
+        .push(id);
+
+    notify(group, body, vec![]);
+}
+
+pub fn clear_failed(ids: &[u64]) {
+    for entry in GROUPS.lock().expect("Couldn't lock mutex").values_mut() {
+        entry.failed.retain(|id| !ids.contains(id));
+    }
+}
@@ -1,7 +1,11 @@
-use std::mem;
+use std::{
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use iced::{
-    Event, Length,
+    Event, Length, Task,
     alignment::Horizontal,
     event::{self, Status},
     keyboard::{
@@ -10,22 +14,33 @@ use iced::{
         key::Named::{ArrowDown, ArrowLeft, ArrowRight, ArrowUp, Enter, Escape},
     },
     never,
-    widget::{Space, column, container, rich_text, row, span},
+    widget::{Id, Space, column, container, operation::focus, rich_text, row, span},
 };
-use reqwest::Client;
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use crate::{
-    app,
+    app, catalogue_cache,
     config::Config,
+    download::{Download, DownloadBackend, format_bytes},
+    http_client::HttpClient,
     list_query_state::ListQueryState,
     main_menu_page::{self, MainMenuPage},
     page::{AppUpdate, Page},
-    presets::{options_list, options_slider, options_tick, square_box},
-    scraper::ScraperImpl,
+    presets::{options_input, options_list, options_slider, options_tick, square_box},
+    scraper::{ScraperImpl, mirror::Audio},
     themes::Themes,
 };
 
+const MAX_DOWNLOADS: usize = 8;
+const RATE_LIMITS: [u64; 8] = [0, 256, 512, 1024, 2048, 4096, 8192, 16384];
+const QUOTAS: [u64; 8] = [0, 10, 25, 50, 100, 250, 500, 1000];
+const CLEANUP_DAYS: [u64; 6] = [0, 1, 3, 7, 14, 30];
+const DOWNLOAD_DIR_ID: &str = "download_dir";
+const FILENAME_TEMPLATE_ID: &str = "filename_template";
+#[allow(clippy::literal_string_with_formatting_args)]
+const TEMPLATE_PLACEHOLDERS: &str = "{title} {slug} {episode:2} {audio} {dub} {scraper}";
+
 #[derive(Debug, Clone, Copy)]
 pub enum Channel {
     Red(f32),
@@ -44,6 +59,17 @@ impl Default for Channel {
 pub enum Message {
     UpdateScraper(ScraperImpl),
     UpdateSaveOnQuit(bool),
+    UpdateAudio(Audio),
+    UpdateDownloadBackend(DownloadBackend),
+    UpdateMaxDownloads(usize),
+    UpdateMaxDownloadsPerHost(usize),
+    UpdateRateLimit(u64),
+    UpdateDownloadDir(String),
+    UpdateFilenameTemplate(String),
+    UpdateMediaServerLayout(bool),
+    UpdateStorageQuota(u64),
+    UpdateAnimeQuota(u64),
+    UpdateCleanupWatched(u64),
     UpdateTheme(Themes),
     Background(Channel),
     Text(Channel),
@@ -56,6 +82,17 @@ pub enum Options {
     #[default]
     Scraper,
     SaveOnQuit,
+    Audio,
+    DownloadBackend,
+    MaxDownloads,
+    MaxDownloadsPerHost,
+    RateLimit,
+    DownloadDir,
+    FilenameTemplate,
+    MediaServerLayout,
+    StorageQuota,
+    AnimeQuota,
+    CleanupWatched,
     Theme,
     Background(Channel),
     Text(Channel),
@@ -66,7 +103,18 @@ impl Options {
     pub const fn next(self) -> Self {
         match self {
             Self::Scraper => Self::SaveOnQuit,
-            Self::SaveOnQuit => Self::Theme,
+            Self::SaveOnQuit => Self::Audio,
+            Self::Audio => Self::DownloadBackend,
+            Self::DownloadBackend => Self::MaxDownloads,
+            Self::MaxDownloads => Self::MaxDownloadsPerHost,
+            Self::MaxDownloadsPerHost => Self::RateLimit,
+            Self::RateLimit => Self::DownloadDir,
+            Self::DownloadDir => Self::FilenameTemplate,
+            Self::FilenameTemplate => Self::MediaServerLayout,
+            Self::MediaServerLayout => Self::StorageQuota,
+            Self::StorageQuota => Self::AnimeQuota,
+            Self::AnimeQuota => Self::CleanupWatched,
+            Self::CleanupWatched => Self::Theme,
             Self::Theme => Self::Background(Channel::Red(0.0)),
             Self::Background(Channel::Red(_)) => Self::Background(Channel::Green(0.0)),
             Self::Background(Channel::Green(_)) => Self::Background(Channel::Blue(0.0)),
@@ -87,7 +135,18 @@ impl Options {
     pub const fn prev(self) -> Self {
         match self {
             Self::Scraper | Self::SaveOnQuit => Self::Scraper,
-            Self::Theme => Self::SaveOnQuit,
+            Self::Audio => Self::SaveOnQuit,
+            Self::DownloadBackend => Self::Audio,
+            Self::MaxDownloads => Self::DownloadBackend,
+            Self::MaxDownloadsPerHost => Self::MaxDownloads,
+            Self::RateLimit => Self::MaxDownloadsPerHost,
+            Self::DownloadDir => Self::RateLimit,
+            Self::FilenameTemplate => Self::DownloadDir,
+            Self::MediaServerLayout => Self::FilenameTemplate,
+            Self::StorageQuota => Self::MediaServerLayout,
+            Self::AnimeQuota => Self::StorageQuota,
+            Self::CleanupWatched => Self::AnimeQuota,
+            Self::Theme => Self::CleanupWatched,
             Self::Background(Channel::Red(_)) => Self::Theme,
             Self::Background(Channel::Green(_)) => Self::Background(Channel::Red(0.0)),
             Self::Background(Channel::Blue(_)) => Self::Background(Channel::Green(0.0)),
@@ -107,9 +166,11 @@ impl Options {
 pub struct OptionsPage {
     pub old_config: Config,
     pub config: Config,
-    pub client: Client,
+    pub client: HttpClient,
     pub anime_list: ListQueryState,
+    pub download: Arc<Download>,
     pub selection: Options,
+    pub download_dir: String,
 }
 
 impl Page for OptionsPage {
@@ -118,9 +179,10 @@ impl Page for OptionsPage {
         square_box(column![
             row![
                 column![
-                    options_list::<ScraperImpl>(
+                    options_list(
                         "Scraper: ",
                         matches!(self.selection, Options::Scraper),
+                        ScraperImpl::iter(),
                         Some(self.config.scraper.to_string()),
                         |selected| {
                             app::Message::Options(Message::UpdateScraper(
@@ -134,9 +196,136 @@ impl Page for OptionsPage {
                         self.config.save_on_quit,
                         |selected| { app::Message::Options(Message::UpdateSaveOnQuit(selected)) }
                     ),
-                    options_list::<Themes>(
+                    options_list(
+                        "Audio preferido: ",
+                        matches!(self.selection, Options::Audio),
+                        Audio::iter()
+                            .filter(|audio| self.config.scraper.capabilities().dub
+                                || *audio == Audio::Sub),
+                        Some(self.config.audio.to_string()),
+                        |selected| {
+                            app::Message::Options(Message::UpdateAudio(
+                                selected.parse::<Audio>().expect("Shouldn't happen"),
+                            ))
+                        }
+                    ),
+                    options_list(
+                        "Descargador: ",
+                        matches!(self.selection, Options::DownloadBackend),
+                        DownloadBackend::iter(),
+                        Some(self.config.download_backend.to_string()),
+                        |selected| {
+                            app::Message::Options(Message::UpdateDownloadBackend(
+                                selected
+                                    .parse::<DownloadBackend>()
+                                    .expect("Shouldn't happen"),
+                            ))
+                        }
+                    ),
+                    options_list(
+                        "Descargas simultáneas: ",
+                        matches!(self.selection, Options::MaxDownloads),
+                        1..=MAX_DOWNLOADS,
+                        Some(self.config.max_downloads.to_string()),
+                        |selected| {
+                            app::Message::Options(Message::UpdateMaxDownloads(
+                                selected.parse().expect("Shouldn't happen"),
+                            ))
+                        }
+                    ),
+                    options_list(
+                        "Descargas por servidor: ",
+                        matches!(self.selection, Options::MaxDownloadsPerHost),
+                        (0..=MAX_DOWNLOADS).map(format_limit),
+                        Some(format_limit(self.config.max_downloads_per_host)),
+                        |selected| {
+                            app::Message::Options(Message::UpdateMaxDownloadsPerHost(
+                                selected.parse().unwrap_or_default(),
+                            ))
+                        }
+                    ),
+                    options_list(
+                        "Límite de velocidad: ",
+                        matches!(self.selection, Options::RateLimit),
+                        RATE_LIMITS.map(format_rate),
+                        Some(format_rate(self.config.rate_limit_kib)),
+                        |selected| {
+                            app::Message::Options(Message::UpdateRateLimit(parse_step(
+                                &RATE_LIMITS,
+                                format_rate,
+                                &selected,
+                            )))
+                        }
+                    ),
+                    options_input(
+                        if valid_download_dir(&self.download_dir) {
+                            "Carpeta de descargas: "
+                        } else {
+                            "Carpeta de descargas (debe ser una ruta absoluta): "
+                        },
+                        matches!(self.selection, Options::DownloadDir),
+                        DOWNLOAD_DIR_ID,
+                        &self.download_dir,
+                        |dir| app::Message::Options(Message::UpdateDownloadDir(dir))
+                    ),
+                    options_input(
+                        &format!("Nombre de archivo ({TEMPLATE_PLACEHOLDERS}): "),
+                        matches!(self.selection, Options::FilenameTemplate),
+                        FILENAME_TEMPLATE_ID,
+                        &self.config.filename_template,
+                        |template| app::Message::Options(Message::UpdateFilenameTemplate(template))
+                    ),
+                    options_tick(
+                        "Estructura para servidor multimedia (Jellyfin, Kodi): ",
+                        matches!(self.selection, Options::MediaServerLayout),
+                        self.config.media_server_layout,
+                        |selected| {
+                            app::Message::Options(Message::UpdateMediaServerLayout(selected))
+                        }
+                    ),
+                    options_list(
+                        "Cuota de almacenamiento: ",
+                        matches!(self.selection, Options::StorageQuota),
+                        QUOTAS.map(format_quota),
+                        Some(format_quota(self.config.storage_quota_gib)),
+                        |selected| {
+                            app::Message::Options(Message::UpdateStorageQuota(parse_step(
+                                &QUOTAS,
+                                format_quota,
+                                &selected,
+                            )))
+                        }
+                    ),
+                    options_list(
+                        "Cuota por anime: ",
+                        matches!(self.selection, Options::AnimeQuota),
+                        QUOTAS.map(format_quota),
+                        Some(format_quota(self.config.anime_quota_gib)),
+                        |selected| {
+                            app::Message::Options(Message::UpdateAnimeQuota(parse_step(
+                                &QUOTAS,
+                                format_quota,
+                                &selected,
+                            )))
+                        }
+                    ),
+                    options_list(
+                        "Borrar episodios vistos tras: ",
+                        matches!(self.selection, Options::CleanupWatched),
+                        CLEANUP_DAYS.map(format_days),
+                        Some(format_days(self.config.cleanup_watched_days)),
+                        |selected| {
+                            app::Message::Options(Message::UpdateCleanupWatched(parse_step(
+                                &CLEANUP_DAYS,
+                                format_days,
+                                &selected,
+                            )))
+                        }
+                    ),
+                    options_list(
                         "Esquema de colores: ",
                         matches!(self.selection, Options::Theme),
+                        Themes::iter(),
                         Some(self.config.theme.to_string()),
                         |selected| {
                             app::Message::Options(Message::UpdateTheme(
@@ -258,6 +447,53 @@ impl Page for OptionsPage {
                     self.config.save_on_quit = selected;
                     AppUpdate::None
                 }
+                Message::UpdateAudio(audio) => {
+                    self.config.audio = audio;
+                    AppUpdate::None
+                }
+                Message::UpdateDownloadBackend(backend) => {
+                    self.config.download_backend = backend;
+                    AppUpdate::None
+                }
+                Message::UpdateMaxDownloads(max) => {
+                    self.config.max_downloads = max;
+                    AppUpdate::None
+                }
+                Message::UpdateMaxDownloadsPerHost(max) => {
+                    self.config.max_downloads_per_host = max;
+                    AppUpdate::None
+                }
+                Message::UpdateRateLimit(limit) => {
+                    self.config.rate_limit_kib = limit;
+                    AppUpdate::None
+                }
+                Message::UpdateDownloadDir(dir) => {
+                    if valid_download_dir(&dir) {
+                        self.config.download_dir = PathBuf::from(&dir);
+                    }
+                    self.download_dir = dir;
+                    AppUpdate::None
+                }
+                Message::UpdateFilenameTemplate(template) => {
+                    self.config.filename_template = template;
+                    AppUpdate::None
+                }
+                Message::UpdateMediaServerLayout(selected) => {
+                    self.config.media_server_layout = selected;
+                    AppUpdate::None
+                }
+                Message::UpdateStorageQuota(quota) => {
+                    self.config.storage_quota_gib = quota;
+                    AppUpdate::None
+                }
+                Message::UpdateAnimeQuota(quota) => {
+                    self.config.anime_quota_gib = quota;
+                    AppUpdate::None
+                }
+                Message::UpdateCleanupWatched(days) => {
+                    self.config.cleanup_watched_days = days;
+                    AppUpdate::None
+                }
                 Message::UpdateTheme(theme) => {
                     self.config.theme = theme;
                     self.config.palette = self.theme().palette().into();
@@ -335,6 +571,55 @@ impl Page for OptionsPage {
                             self.config.save_on_quit = !self.config.save_on_quit;
                             AppUpdate::None
                         }
+                        Options::Audio if !self.config.scraper.capabilities().dub => {
+                            AppUpdate::None
+                        }
+                        Options::Audio => {
+                            self.config.audio = self.config.audio.next();
+                            AppUpdate::None
+                        }
+                        Options::DownloadBackend => {
+                            self.config.download_backend = self.config.download_backend.next();
+                            AppUpdate::None
+                        }
+                        Options::MaxDownloads => {
+                            self.config.max_downloads =
+                                (self.config.max_downloads + 1).min(MAX_DOWNLOADS);
+                            AppUpdate::None
+                        }
+                        Options::MaxDownloadsPerHost => {
+                            self.config.max_downloads_per_host =
+                                (self.config.max_downloads_per_host + 1).min(MAX_DOWNLOADS);
+                            AppUpdate::None
+                        }
+                        Options::RateLimit => {
+                            self.config.rate_limit_kib =
+                                next_step(&RATE_LIMITS, self.config.rate_limit_kib);
+                            AppUpdate::None
+                        }
+                        Options::DownloadDir => AppUpdate::Task(focus(Id::new(DOWNLOAD_DIR_ID))),
+                        Options::FilenameTemplate => {
+                            AppUpdate::Task(focus(Id::new(FILENAME_TEMPLATE_ID)))
+                        }
+                        Options::MediaServerLayout => {
+                            self.config.media_server_layout = !self.config.media_server_layout;
+                            AppUpdate::None
+                        }
+                        Options::StorageQuota => {
+                            self.config.storage_quota_gib =
+                                next_step(&QUOTAS, self.config.storage_quota_gib);
+                            AppUpdate::None
+                        }
+                        Options::AnimeQuota => {
+                            self.config.anime_quota_gib =
+                                next_step(&QUOTAS, self.config.anime_quota_gib);
+                            AppUpdate::None
+                        }
+                        Options::CleanupWatched => {
+                            self.config.cleanup_watched_days =
+                                next_step(&CLEANUP_DAYS, self.config.cleanup_watched_days);
+                            AppUpdate::None
+                        }
                         Options::Theme => {
                             self.config.theme = self.config.theme.next();
                             self.config.palette = self.theme().palette().into();
@@ -428,6 +713,52 @@ impl Page for OptionsPage {
                             self.config.save_on_quit = !self.config.save_on_quit;
                             AppUpdate::None
                         }
+                        Options::Audio if !self.config.scraper.capabilities().dub => {
+                            AppUpdate::None
+                        }
+                        Options::Audio => {
+                            self.config.audio = self.config.audio.prev();
+                            AppUpdate::None
+                        }
+                        Options::DownloadBackend => {
+                            self.config.download_backend = self.config.download_backend.prev();
+                            AppUpdate::None
+                        }
+                        Options::MaxDownloads => {
+                            self.config.max_downloads =
+                                self.config.max_downloads.saturating_sub(1).max(1);
+                            AppUpdate::None
+                        }
+                        Options::MaxDownloadsPerHost => {
+                            self.config.max_downloads_per_host =
+                                self.config.max_downloads_per_host.saturating_sub(1);
+                            AppUpdate::None
+                        }
+                        Options::RateLimit => {
+                            self.config.rate_limit_kib =
+                                prev_step(&RATE_LIMITS, self.config.rate_limit_kib);
+                            AppUpdate::None
+                        }
+                        Options::DownloadDir | Options::FilenameTemplate => AppUpdate::None,
+                        Options::MediaServerLayout => {
+                            self.config.media_server_layout = !self.config.media_server_layout;
+                            AppUpdate::None
+                        }
+                        Options::StorageQuota => {
+                            self.config.storage_quota_gib =
+                                prev_step(&QUOTAS, self.config.storage_quota_gib);
+                            AppUpdate::None
+                        }
+                        Options::AnimeQuota => {
+                            self.config.anime_quota_gib =
+                                prev_step(&QUOTAS, self.config.anime_quota_gib);
+                            AppUpdate::None
+                        }
+                        Options::CleanupWatched => {
+                            self.config.cleanup_watched_days =
+                                prev_step(&CLEANUP_DAYS, self.config.cleanup_watched_days);
+                            AppUpdate::None
+                        }
                         Options::Theme => {
                             self.config.theme = self.config.theme.prev();
                             self.config.palette = self.theme().palette().into();
@@ -512,30 +843,45 @@ impl Page for OptionsPage {
                             AppUpdate::None
                         }
                     },
+                    Key::Named(Enter) if !valid_download_dir(&self.download_dir) => {
+                        AppUpdate::Task(focus(Id::new(DOWNLOAD_DIR_ID)))
+                    }
                     Key::Named(Enter) => {
                         let anime_list = if self.config.scraper == self.old_config.scraper {
                             mem::take(&mut self.anime_list)
                         } else {
-                            ListQueryState::spawn(self.config.scraper, mem::take(&mut self.client))
+                            ListQueryState::spawn(
+                                self.config.scraper,
+                                self.client.clone(),
+                                self.config.catalogue_ttl(),
+                            )
                         };
 
                         self.config.save().expect("Couldn't save config");
+                        let config = self.config.clone();
 
-                        AppUpdate::Page(Box::new(MainMenuPage {
-                            config: mem::take(&mut self.config),
-                            client: self.client.clone(),
-                            selection: main_menu_page::Selection::Options,
-                            anime_list,
-                            waiting: false,
-                        }))
+                        AppUpdate::Both((
+                            Box::new(MainMenuPage {
+                                catalogue_updated: catalogue_cache::updated(self.config.scraper),
+                                config: mem::take(&mut self.config),
+                                client: self.client.clone(),
+                                selection: main_menu_page::Selection::Options,
+                                anime_list,
+                                download: self.download.clone(),
+                                waiting: false,
+                            }),
+                            Task::done(app::Message::Config(Box::new(config))),
+                        ))
                     }
                     Key::Character("q") | Key::Named(Escape) => {
                         self.config.theme = self.old_config.theme;
                         AppUpdate::Page(Box::new(MainMenuPage {
+                            catalogue_updated: catalogue_cache::updated(self.old_config.scraper),
                             config: mem::take(&mut self.old_config),
                             client: mem::take(&mut self.client),
                             selection: main_menu_page::Selection::Options,
                             anime_list: mem::take(&mut self.anime_list),
+                            download: self.download.clone(),
                             waiting: false,
                         }))
                     }
@@ -560,3 +906,64 @@ impl Page for OptionsPage {
         self.config.theme()
     }
 }
+
+fn valid_download_dir(dir: &str) -> bool {
+    !dir.trim().is_empty() && Path::new(dir).is_absolute()
+}
+
+fn next_step(steps: &[u64], current: u64) -> u64 {
+    steps
+        .iter()
+        .copied()
+        .find(|step| *step > current)
+        .unwrap_or_else(|| steps.last().copied().unwrap_or(current))
+}
+
+fn prev_step(steps: &[u64], current: u64) -> u64 {
+    steps
+        .iter()
+        .rev()
+        .copied()
+        .find(|step| *step < current)
+        .unwrap_or_default()
+}
+
+fn parse_step(steps: &[u64], format: fn(u64) -> String, selected: &str) -> u64 {
+    steps
+        .iter()
+        .copied()
+        .find(|step| format(*step) == selected)
+        .unwrap_or_default()
+}
+
+fn format_quota(gib: u64) -> String {
+    if gib == 0 {
+        "Sin límite".to_owned()
+    } else {
+        format!("{gib} GB")
+    }
+}
+
+fn format_days(days: u64) -> String {
+    match days {
+        0 => "Nunca".to_owned(),
+        1 => "1 día".to_owned(),
+        days => format!("{days} días"),
+    }
+}
+
+fn format_rate(kib: u64) -> String {
+    if kib == 0 {
+        "Sin límite".to_owned()
+    } else {
+        format!("{}/s", format_bytes(kib * 1024))
+    }
+}
+
+fn format_limit(limit: usize) -> String {
+    if limit == 0 {
+        "Sin límite".to_owned()
+    } else {
+        limit.to_string()
+    }
+}
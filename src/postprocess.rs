@@ -0,0 +1,234 @@
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::{
+    ffi::OsString,
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::download::Progress;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PostProfile {
+    pub name: String,
+    pub extension: String,
+    pub args: Vec<String>,
+}
+
+impl PostProfile {
+    fn new(name: &str, extension: &str, args: &[&str]) -> Self {
+        Self {
+            name: name.to_owned(),
+            extension: extension.to_owned(),
+            args: args.iter().map(|arg| (*arg).to_owned()).collect(),
+        }
+    }
+}
+
+pub fn default_profiles() -> Vec<PostProfile> {
+    vec![
+        PostProfile::new("mkv", "mkv", &["-map", "0", "-c", "copy"]),
+        PostProfile::new(
+            "h265",
+            "mkv",
+            &[
+                "-map", "0", "-c", "copy", "-c:v", "libx265", "-crf", "23", "-preset", "medium",
+            ],
+        ),
+        PostProfile::new(
+            "normalizar-audio",
+            "mkv",
+            &[
+                "-map",
+                "0",
+                "-c",
+                "copy",
+                "-af",
+                "loudnorm=I=-16:TP=-1.5:LRA=11",
+                "-c:a",
+                "aac",
+                "-b:a",
+                "192k",
+            ],
+        ),
+    ]
+}
+
+pub fn next_profile(profiles: &[PostProfile], current: Option<&str>) -> Option<String> {
+    current
+        .map_or_else(
+            || profiles.first(),
+            |current| {
+                profiles
+                    .iter()
+                    .skip_while(|profile| profile.name != current)
+                    .nth(1)
+            },
+        )
+        .map(|profile| profile.name.clone())
+}
+
+pub fn run(profile: &PostProfile, input: &Path, progress: &Progress) -> Result<PathBuf, String> {
+    let (temporary, output) = output_paths(input, &profile.extension);
+    let duration = duration(input);
+
+    let mut command = command("ffmpeg");
+    let mut child = command
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-y")
+        .arg("-i")
+        .arg(input)
+        .args(&profile.args)
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats")
+        .arg(&temporary)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("no se ha podido ejecutar ffmpeg: {err}"))?;
+
+    let stderr = child.stderr.take().unwrap();
+    let stderr = thread::spawn(move || {
+        BufReader::new(stderr)
+            .lines()
+            .map_while(Result::ok)
+            .collect::<Vec<_>>()
+    });
+
+    let stdout = child.stdout.take().unwrap();
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if progress.is_interrupted() {
+            let _ = child.kill();
+            break;
+        }
+
+        if let (Some(seconds), Some(duration)) = (out_time(&line), duration) {
+            #[allow(clippy::cast_possible_truncation)]
+            progress.set_percent((seconds / duration * 100.0).clamp(0.0, 100.0) as f32);
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|err| format!("ffmpeg no ha terminado correctamente: {err}"))?;
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() || progress.is_interrupted() {
+        let _ = fs::remove_file(&temporary);
+        return Err(stderr
+            .last()
+            .filter(|_| !progress.is_interrupted())
+            .map_or_else(|| "interrumpida".to_owned(), |line| line.trim().to_owned()));
+    }
+
+    if input != output {
+        fs::remove_file(input).map_err(|err| err.to_string())?;
+    }
+    if output.exists() {
+        fs::remove_file(&output).map_err(|err| err.to_string())?;
+    }
+    fs::rename(&temporary, &output).map_err(|err| err.to_string())?;
+
+    Ok(output)
+}
+
+fn output_paths(input: &Path, extension: &str) -> (PathBuf, PathBuf) {
+    let stem = input.with_extension("");
+
+    let mut temporary = OsString::from(stem.as_os_str());
+    temporary.push(format!(".part.{extension}"));
+
+    let mut output = OsString::from(stem.as_os_str());
+    output.push(format!(".{extension}"));
+
+    (PathBuf::from(temporary), PathBuf::from(output))
+}
+
+fn out_time(line: &str) -> Option<f64> {
+    let micros = line
+        .strip_prefix("out_time_us=")
+        .or_else(|| line.strip_prefix("out_time_ms="))?;
+    micros
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|micros| micros / 1_000_000.0)
+}
+
+fn duration(input: &Path) -> Option<f64> {
+    let output = command("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format=duration")
+        .arg("-of")
+        .arg("default=noprint_wrappers=1:nokey=1")
+        .arg(input)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|duration| *duration > 0.0)
+}
+
+fn command(name: &str) -> Command {
+    #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
+    let mut command = Command::new(format!(
+        "{name}{}",
+        if cfg!(target_os = "windows") {
+            ".exe"
+        } else {
+            ""
+        }
+    ));
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000);
+
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_to_a_partial_file_next_to_the_input() {
+        let (temporary, output) = output_paths(Path::new("/videos/show/show-1.5.mp4"), "mkv");
+
+        assert_eq!(temporary, PathBuf::from("/videos/show/show-1.5.part.mkv"));
+        assert_eq!(output, PathBuf::from("/videos/show/show-1.5.mkv"));
+    }
+
+    #[test]
+    fn cycles_through_profiles_and_back_to_none() {
+        let profiles = default_profiles();
+
+        assert_eq!(next_profile(&profiles, None).as_deref(), Some("mkv"));
+        assert_eq!(
+            next_profile(&profiles, Some("mkv")).as_deref(),
+            Some("h265")
+        );
+        assert_eq!(next_profile(&profiles, Some("normalizar-audio")), None);
+        assert_eq!(next_profile(&profiles, Some("borrado")), None);
+    }
+
+    #[test]
+    fn parses_ffmpeg_progress() {
+        assert_eq!(out_time("out_time_us=1500000"), Some(1.5));
+        assert_eq!(out_time("out_time_ms=3000000"), Some(3.0));
+        assert_eq!(out_time("out_time_us=N/A"), None);
+        assert_eq!(out_time("frame=12"), None);
+    }
+}
@@ -3,10 +3,10 @@ use iced::{
     Border, Element, Length, Shadow,
     overlay::menu,
     widget::{
-        Button, Container, Space, button, column, container, pick_list, row, slider, text, toggler,
+        Button, Container, Id, Space, button, column, container, pick_list, row, slider, text,
+        text_input, toggler,
     },
 };
-use strum::IntoEnumIterator;
 
 use crate::app;
 
@@ -90,9 +90,10 @@ pub fn transparent_button_cond<'a, Message: 'a>(
         .padding(0)
 }
 
-pub fn options_list<'a, T: IntoEnumIterator + ToString>(
+pub fn options_list<'a, T: ToString>(
     name: &str,
     selected: bool,
+    options: impl IntoIterator<Item = T>,
     current: Option<String>,
     callback: impl Fn(String) -> app::Message + 'a,
 ) -> Container<'a, app::Message> {
@@ -102,7 +103,10 @@ pub fn options_list<'a, T: IntoEnumIterator + ToString>(
             row![
                 Space::new().width(Length::Fixed(18.0)),
                 pick_list(
-                    T::iter().map(|t| t.to_string()).collect::<Vec<_>>(),
+                    options
+                        .into_iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<_>>(),
                     current,
                     callback,
                 )
@@ -143,6 +147,39 @@ pub fn options_list<'a, T: IntoEnumIterator + ToString>(
     )
 }
 
+pub fn options_input<'a>(
+    name: &str,
+    selected: bool,
+    id: &'static str,
+    current: &str,
+    callback: impl Fn(String) -> app::Message + 'a,
+) -> Container<'a, app::Message> {
+    Container::new(
+        column![
+            transparent_button(name, selected),
+            row![
+                Space::new().width(Length::Fixed(18.0)),
+                text_input("", current)
+                    .id(Id::new(id))
+                    .style(|theme: &iced::Theme, _| text_input::Style {
+                        background: iced::Background::Color(theme.palette().background),
+                        border: Border {
+                            color: theme.palette().primary,
+                            width: 1.0,
+                            ..Default::default()
+                        },
+                        icon: theme.palette().primary,
+                        placeholder: highlight(theme.palette().text, 20.0),
+                        value: theme.palette().text,
+                        selection: theme.palette().primary,
+                    })
+                    .on_input(callback)
+            ]
+        ]
+        .spacing(6),
+    )
+}
+
 pub fn options_tick<'a>(
     name: &str,
     selected: bool,
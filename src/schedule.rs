@@ -0,0 +1,197 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::local_time;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(u16);
+
+impl TimeOfDay {
+    pub const fn new(hour: u16, minute: u16) -> Self {
+        Self(hour * 60 + minute)
+    }
+
+    fn now() -> (u8, Self) {
+        Self::at(local_time::now())
+    }
+
+    fn at(time: OffsetDateTime) -> (u8, Self) {
+        (
+            time.weekday().number_from_monday(),
+            Self::new(u16::from(time.hour()), u16::from(time.minute())),
+        )
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("hora no válida: {s}");
+        let (hour, minute) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let hour = hour.parse::<u16>().map_err(|_| invalid())?;
+        let minute = minute.parse::<u16>().map_err(|_| invalid())?;
+
+        if minute >= 60 || hour > 24 || (hour == 24 && minute > 0) {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(hour, minute))
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(value: TimeOfDay) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadWindow {
+    #[serde(default)]
+    pub days: Vec<u8>,
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl DownloadWindow {
+    fn applies_on(&self, day: u8) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn contains(&self, day: u8, time: TimeOfDay) -> bool {
+        match self.start.cmp(&self.end) {
+            Ordering::Less => self.applies_on(day) && (self.start..self.end).contains(&time),
+            Ordering::Equal => self.applies_on(day),
+            Ordering::Greater => {
+                (self.applies_on(day) && time >= self.start)
+                    || (self.applies_on(previous_day(day)) && time < self.end)
+            }
+        }
+    }
+}
+
+pub fn is_open(windows: &[DownloadWindow]) -> bool {
+    let (day, time) = TimeOfDay::now();
+    is_open_at(windows, day, time)
+}
+
+pub fn next_opening(windows: &[DownloadWindow]) -> Option<TimeOfDay> {
+    let (day, time) = TimeOfDay::now();
+    next_opening_at(windows, day, time)
+}
+
+fn is_open_at(windows: &[DownloadWindow], day: u8, time: TimeOfDay) -> bool {
+    windows.is_empty() || windows.iter().any(|window| window.contains(day, time))
+}
+
+fn next_opening_at(windows: &[DownloadWindow], day: u8, time: TimeOfDay) -> Option<TimeOfDay> {
+    (0..=7_u16)
+        .flat_map(|offset| {
+            let day = (day - 1 + u8::try_from(offset).unwrap_or_default()) % 7 + 1;
+            windows
+                .iter()
+                .filter(move |window| window.applies_on(day))
+                .map(move |window| (offset * MINUTES_PER_DAY + window.start.0, window.start))
+        })
+        .filter(|(minutes, _)| *minutes > time.0)
+        .min_by_key(|(minutes, _)| *minutes)
+        .map(|(_, start)| start)
+}
+
+const fn previous_day(day: u8) -> u8 {
+    if day <= 1 { 7 } else { day - 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(days: &[u8], start: &str, end: &str) -> DownloadWindow {
+        DownloadWindow {
+            days: days.to_vec(),
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn parses_times_of_day() {
+        assert_eq!("01:30".parse(), Ok(TimeOfDay::new(1, 30)));
+        assert_eq!("24:00".parse(), Ok(TimeOfDay::new(24, 0)));
+        assert!("7".parse::<TimeOfDay>().is_err());
+        assert!("12:60".parse::<TimeOfDay>().is_err());
+        assert_eq!(TimeOfDay::new(7, 5).to_string(), "07:05");
+    }
+
+    #[test]
+    fn checks_windows_on_their_days() {
+        let windows = [window(&[1, 2, 3, 4, 5], "01:00", "07:00")];
+
+        assert!(is_open_at(&[], 3, TimeOfDay::new(12, 0)));
+        assert!(is_open_at(&windows, 1, TimeOfDay::new(1, 0)));
+        assert!(!is_open_at(&windows, 1, TimeOfDay::new(7, 0)));
+        assert!(!is_open_at(&windows, 6, TimeOfDay::new(3, 0)));
+    }
+
+    #[test]
+    fn windows_can_span_midnight() {
+        let windows = [window(&[5], "22:00", "06:00")];
+
+        assert!(is_open_at(&windows, 5, TimeOfDay::new(23, 0)));
+        assert!(is_open_at(&windows, 6, TimeOfDay::new(5, 59)));
+        assert!(!is_open_at(&windows, 5, TimeOfDay::new(5, 0)));
+        assert!(!is_open_at(&windows, 6, TimeOfDay::new(22, 0)));
+    }
+
+    #[test]
+    fn finds_the_next_opening() {
+        let windows = [window(&[], "01:00", "07:00")];
+        assert_eq!(
+            next_opening_at(&windows, 3, TimeOfDay::new(12, 0)),
+            Some(TimeOfDay::new(1, 0))
+        );
+
+        let windows = [window(&[6, 7], "09:00", "18:00")];
+        assert_eq!(
+            next_opening_at(&windows, 7, TimeOfDay::new(20, 0)),
+            Some(TimeOfDay::new(9, 0))
+        );
+        assert_eq!(next_opening_at(&[], 1, TimeOfDay::new(0, 0)), None);
+    }
+
+    #[test]
+    fn converts_to_the_local_offset() {
+        let utc = OffsetDateTime::from_unix_timestamp(1_704_670_200).unwrap();
+        let offset = time::UtcOffset::from_hms(2, 0, 0).unwrap();
+        let windows = [window(&[1], "01:00", "07:00")];
+
+        assert_eq!(TimeOfDay::at(utc), (7, TimeOfDay::new(23, 30)));
+        assert_eq!(
+            TimeOfDay::at(utc.to_offset(offset)),
+            (1, TimeOfDay::new(1, 30))
+        );
+
+        let (day, time) = TimeOfDay::at(utc.to_offset(offset));
+        assert!(is_open_at(&windows, day, time));
+    }
+}
@@ -2,21 +2,88 @@ use std::fmt;
 
 use bytes::Bytes;
 use reqwest::blocking;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+pub enum AnimeKind {
+    Tv,
+    Movie,
+    Ova,
+    Ona,
+    Special,
+}
+
+impl fmt::Display for AnimeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tv => write!(f, "TV"),
+            Self::Movie => write!(f, "Película"),
+            Self::Ova => write!(f, "OVA"),
+            Self::Ona => write!(f, "ONA"),
+            Self::Special => write!(f, "Especial"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+pub enum AnimeStatus {
+    Airing,
+    Finished,
+    Upcoming,
+}
+
+impl fmt::Display for AnimeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Airing => write!(f, "En emisión"),
+            Self::Finished => write!(f, "Finalizado"),
+            Self::Upcoming => write!(f, "Próximamente"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct Anime {
-    pub names: Vec<String>,
+    pub title: String,
+    pub alt_titles: Vec<String>,
+    pub slug: String,
+    pub id: String,
     pub synopsis: String,
     pub image_url: String,
+    pub genres: Vec<String>,
+    pub year: Option<u16>,
+    pub kind: Option<AnimeKind>,
+    pub status: Option<AnimeStatus>,
+    pub episode_count: Option<u32>,
 }
 
 impl fmt::Display for Anime {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.names[0])
+        write!(f, "{}", self.title)
     }
 }
 
 impl Anime {
+    pub fn titles(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.title.as_str()).chain(self.alt_titles.iter().map(String::as_str))
+    }
+
+    pub fn details(&self) -> String {
+        let kind = self.kind.map(|kind| kind.to_string());
+        let year = self.year.map(|year| year.to_string());
+        let status = self.status.map(|status| status.to_string());
+        let episodes = self.episode_count.map(|count| match count {
+            1 => "1 episodio".to_owned(),
+            _ => format!("{count} episodios"),
+        });
+
+        [kind, year, status, episodes]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+
     pub fn get_image(&self) -> Bytes {
         blocking::Client::default()
             .get(&self.image_url)
@@ -1,170 +1,349 @@
-use iced::futures::{StreamExt, stream};
-use itertools::Itertools;
+use iced::futures::{FutureExt, StreamExt, TryStreamExt, future::BoxFuture, stream};
 use rayon::prelude::*;
-use regex::Regex;
-use reqwest::Client;
-use std::{
-    error::Error,
-    sync::{
-        Arc,
-        atomic::{AtomicUsize, Ordering},
-    },
-    time::Duration,
-};
-use tokio::time::timeout;
+use std::sync::Arc;
+
+use crate::http_client::HttpClient;
 
-use super::{Scraper, anime::Anime};
+use super::{
+    Capabilities, Scraper, ScraperEntry, ScraperResult, SearchProgress,
+    anime::{Anime, AnimeKind, AnimeStatus},
+    episode::{Episode, EpisodeKind},
+    error::ScraperError,
+    js_value::JsValue,
+    mirror::{Audio, Mirror},
+};
 
 const LETTERS: &str = "0ABCDEFGHIJKLMNOPQRSTUVWXYZ";
-const PAGES: i32 = 15;
 
 pub struct AnimeAv1Scraper;
 
+inventory::submit! {
+    ScraperEntry {
+        name: "AnimeAv1Scraper",
+        label: "AnimeAV1",
+        capabilities: Capabilities {
+            progress: true,
+            dub: true,
+            download: true,
+        },
+        scraper: &AnimeAv1Scraper,
+    }
+}
+
 impl Scraper for AnimeAv1Scraper {
-    async fn try_search(
-        client: &Client,
-        progress: Arc<AtomicUsize>,
-    ) -> Result<Vec<Anime>, Box<dyn Error>> {
-        let urls = (1..=PAGES)
-            .cartesian_product(LETTERS.chars())
-            .par_bridge()
-            .map(|(page, letter)| {
-                format!("https://animeav1.com/catalogo?letter={letter}&page={page}")
-            })
-            .collect::<Vec<_>>();
-
-        let total = urls.len();
-
-        let bodies = stream::iter(urls)
-            .map(|url| {
-                let client = client.clone();
-                let completed = progress.clone();
-                tokio::spawn(async move {
-                    let mut retries = 100;
-                    let body = loop {
-                        if let Ok(Ok(response)) =
-                            timeout(Duration::from_millis(1500), client.get(&url).send()).await
-                            && let Ok(body) = response.text().await
-                        {
-                            break body;
-                        }
-
-                        if retries > 0 {
-                            retries -= 1;
-                        } else {
-                            panic!("Max retries reached for {url}");
-                        }
-                    };
-                    completed.fetch_add(1, Ordering::Relaxed);
-                    body
+    fn try_search<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        progress: Arc<SearchProgress>,
+    ) -> BoxFuture<'a, ScraperResult<Vec<Anime>>> {
+        async move {
+            progress.add_total(LETTERS.len());
+
+            let mut animes = stream::iter(LETTERS.chars())
+                .map(|letter| search_letter(client, letter, &progress))
+                .buffer_unordered(LETTERS.len())
+                .try_concat()
+                .await?;
+
+            animes.par_sort();
+
+            Ok(animes)
+        }
+        .boxed()
+    }
+
+    fn try_get_episodes<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        slug: &'a str,
+    ) -> BoxFuture<'a, ScraperResult<Vec<Episode>>> {
+        async move {
+            let anime = client
+                .text(&format!("https://animeav1.com/media/{slug}"))
+                .await?;
+
+            let mut episodes = JsValue::find(&anime, "episodes")?
+                .as_array()
+                .ok_or_else(|| ScraperError::Parse("episodes is not a list".into()))?
+                .iter()
+                .filter_map(parse_episode)
+                .collect::<Vec<_>>();
+
+            episodes.sort_by(|a, b| a.number.total_cmp(&b.number));
+
+            Ok(episodes)
+        }
+        .boxed()
+    }
+
+    fn try_get_mirrors<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        slug: &'a str,
+        episode: f64,
+    ) -> BoxFuture<'a, ScraperResult<Vec<Mirror>>> {
+        async move {
+            let response = client
+                .text(&format!("https://animeav1.com/media/{slug}/{episode}"))
+                .await?;
+
+            let embeds = JsValue::find(&response, "embeds")?;
+            let sections = [Audio::Sub, Audio::Dub]
+                .into_iter()
+                .filter_map(|audio| {
+                    let section = embeds.get(&audio.to_string())?.as_array()?;
+                    Some((audio, section))
                 })
-            })
-            .buffer_unordered(total)
-            .map(|result| result.unwrap())
-            .collect::<String>()
-            .await;
-
-        let anime_list_re = Regex::new(r"results:\s*\[(.*?)\],").unwrap();
-        let anime_list_section = anime_list_re
-            .captures_iter(&bodies)
-            .par_bridge()
-            .filter_map(|c| Some(c.get(0)?.as_str()))
-            .collect::<String>()
-            .replace('\\', "");
-
-        let anime_re =
-            Regex::new(r#"\{id:"(.*?)",title:"(.*?)",synopsis:"(.*?)",categoryId.*?slug:"(.*?)",category.*?\}"#)
-                .unwrap();
-
-        let mut animes = anime_re
-            .captures_iter(&anime_list_section)
-            .par_bridge()
-            .filter_map(|c| {
-                let id = c.get(1)?.as_str();
-                let title = c.get(2)?.as_str();
-                let synopsis = c
-                    .get(3)?
-                    .as_str()
-                    .replace(r"\n", "\n")
-                    .replace(r".nn", ". ");
-                let slug = c.get(4)?.as_str();
-                Some(Anime {
-                    names: vec![title.into(), slug.into()],
-                    synopsis,
-                    image_url: format!("https://cdn.animeav1.com/covers/{id}.jpg"),
+                .collect::<Vec<_>>();
+
+            if sections.is_empty() {
+                return Err(ScraperError::Parse("embeds has no audio sections".into()));
+            }
+
+            let mirrors = sections
+                .into_iter()
+                .flat_map(|(audio, section)| {
+                    section.iter().filter_map(move |mirror| {
+                        Some(Mirror {
+                            url: mirror.get("url")?.as_str()?.to_owned(),
+                            server: mirror
+                                .get("server")
+                                .and_then(JsValue::as_str)
+                                .map(ToOwned::to_owned),
+                            audio,
+                        })
+                    })
                 })
+                .collect::<Vec<_>>();
+
+            Ok(mirrors)
+        }
+        .boxed()
+    }
+}
+
+async fn search_letter(
+    client: &HttpClient,
+    letter: char,
+    progress: &SearchProgress,
+) -> ScraperResult<Vec<Anime>> {
+    let body = client.text(&catalogue_url(letter, 1)).await?;
+    progress.complete();
+
+    let mut animes = parse_catalogue(&body)?;
+
+    if let Some(last_page) = last_page(&body) {
+        progress.add_total(last_page.saturating_sub(1));
+
+        let rest = stream::iter(2..=last_page)
+            .map(|page| async move {
+                let body = client.text(&catalogue_url(letter, page)).await?;
+                progress.complete();
+                parse_catalogue(&body)
             })
-            .collect::<Vec<_>>();
+            .buffer_unordered(last_page)
+            .try_concat()
+            .await?;
 
-        animes.par_sort();
+        animes.extend(rest);
+    } else {
+        let mut page = 1;
+        let mut found = !animes.is_empty();
 
-        Ok(animes)
+        while found {
+            page += 1;
+            progress.add_total(1);
+
+            let body = client.text(&catalogue_url(letter, page)).await?;
+            progress.complete();
+
+            let next = parse_catalogue(&body)?;
+            found = !next.is_empty();
+            animes.extend(next);
+        }
     }
 
-    async fn try_get_episodes(client: &Client, slug: &str) -> Result<Vec<f64>, Box<dyn Error>> {
-        let anime = client
-            .get(format!("https://animeav1.com/media/{slug}"))
-            .send()
-            .await
-            .unwrap()
-            .text()
-            .await
-            .unwrap();
-
-        let episodes_re = Regex::new(r"number:(.*?)}")?;
-        let mut episodes = episodes_re
-            .captures_iter(&anime)
-            .par_bridge()
-            .filter_map(|c| c.get(1))
-            .filter_map(|m| m.as_str().parse::<f64>().ok())
-            .collect::<Vec<_>>();
-
-        episodes.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-        Ok(episodes)
+    Ok(animes)
+}
+
+fn catalogue_url(letter: char, page: usize) -> String {
+    format!("https://animeav1.com/catalogo?letter={letter}&page={page}")
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn last_page(body: &str) -> Option<usize> {
+    ["totalPages", "lastPage"]
+        .into_iter()
+        .find_map(|key| JsValue::find(body, key).ok()?.as_f64())
+        .filter(|pages| pages.is_finite() && *pages >= 1.0)
+        .map(|pages| pages as usize)
+}
+
+fn parse_catalogue(body: &str) -> ScraperResult<Vec<Anime>> {
+    let results = JsValue::find(body, "results")?;
+
+    Ok(results
+        .as_array()
+        .ok_or_else(|| ScraperError::Parse("results is not a list".into()))?
+        .par_iter()
+        .filter_map(|anime| {
+            let id = anime.get("id")?.as_str()?;
+            let title = anime.get("title")?.as_str()?;
+            let synopsis = anime.get("synopsis")?.as_str().unwrap_or_default();
+            let slug = anime.get("slug")?.as_str()?;
+            Some(Anime {
+                title: title.into(),
+                alt_titles: alt_titles(anime, title),
+                slug: slug.into(),
+                id: id.into(),
+                synopsis: synopsis.into(),
+                image_url: format!("https://cdn.animeav1.com/covers/{id}.jpg"),
+                genres: genres(anime),
+                year: year(anime),
+                kind: kind(anime),
+                status: status(anime),
+                episode_count: episode_count(anime),
+            })
+        })
+        .collect())
+}
+
+fn alt_titles(anime: &JsValue, title: &str) -> Vec<String> {
+    let mut titles = Vec::new();
+
+    for key in ["aka", "altTitles", "synonyms"] {
+        let values: Vec<&JsValue> = match anime.get(key) {
+            Some(JsValue::Object(entries)) => entries.iter().map(|(_, value)| value).collect(),
+            Some(JsValue::Array(values)) => values.iter().collect(),
+            Some(value) => vec![value],
+            None => continue,
+        };
+
+        for value in values.into_iter().filter_map(JsValue::as_str) {
+            let value = value.trim();
+            if !value.is_empty() && value != title && !titles.iter().any(|t| t == value) {
+                titles.push(value.to_owned());
+            }
+        }
     }
 
-    async fn try_get_mirrors(
-        client: &Client,
-        slug: &str,
-        episode: f64,
-    ) -> Result<Vec<String>, Box<dyn Error>> {
-        let response = client
-            .get(format!("https://animeav1.com/media/{slug}/{episode}"))
-            .send()
-            .await
-            .unwrap()
-            .text()
-            .await
-            .unwrap();
-
-        let embeds_re = Regex::new(r"embeds:\s*\{([^}]*\{[^}]*\})*[^}]*\}")?;
-        let embeds_section = embeds_re
-            .captures(&response)
-            .and_then(|c| c.get(0))
-            .ok_or("Embeds section not found")?
-            .as_str();
-
-        let sub_re = Regex::new(r"SUB:\s*\[([^]]*\{[^]]*\})*[^]]*\]")?;
-        let sub_section = sub_re
-            .captures(embeds_section)
-            .and_then(|c| c.get(0))
-            .ok_or("Sub section not found")?
-            .as_str();
-
-        // Then find all URLs within the embeds section
-        let url_re = Regex::new(r#"url:\s*"([^"]+)"#)?;
-        let mirrors: Vec<String> = url_re
-            .captures_iter(sub_section)
-            .par_bridge()
-            .filter_map(|c| c.get(1))
-            .map(|m| m.as_str().to_string())
-            .collect::<Vec<_>>();
-
-        Ok(mirrors)
+    titles
+}
+
+fn genres(anime: &JsValue) -> Vec<String> {
+    anime
+        .get("genres")
+        .and_then(JsValue::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|genre| genre.as_str().or_else(|| genre.get("name")?.as_str()))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn year(anime: &JsValue) -> Option<u16> {
+    if let Some(year) = anime.get("year").and_then(JsValue::as_f64) {
+        return (1900.0..=9999.0).contains(&year).then_some(year as u16);
+    }
+
+    anime
+        .get("startDate")
+        .and_then(JsValue::as_str)?
+        .get(..4)?
+        .parse()
+        .ok()
+}
+
+fn kind(anime: &JsValue) -> Option<AnimeKind> {
+    let category = anime.get("category");
+    let name =
+        category.and_then(|category| category.as_str().or_else(|| category.get("name")?.as_str()));
+
+    if let Some(name) = name {
+        return match name.to_lowercase().as_str() {
+            "tv" | "tv anime" | "anime" => Some(AnimeKind::Tv),
+            "película" | "pelicula" | "movie" => Some(AnimeKind::Movie),
+            "ova" => Some(AnimeKind::Ova),
+            "ona" => Some(AnimeKind::Ona),
+            "especial" | "special" => Some(AnimeKind::Special),
+            _ => None,
+        };
+    }
+
+    match anime.get("categoryId")?.as_f64()? {
+        1.0 => Some(AnimeKind::Tv),
+        2.0 => Some(AnimeKind::Movie),
+        3.0 => Some(AnimeKind::Ova),
+        4.0 => Some(AnimeKind::Special),
+        _ => None,
+    }
+}
+
+fn status(anime: &JsValue) -> Option<AnimeStatus> {
+    match anime.get("status")?.as_str()?.to_lowercase().as_str() {
+        "en emisión" | "en emision" | "emision" | "airing" => Some(AnimeStatus::Airing),
+        "finalizado" | "finished" => Some(AnimeStatus::Finished),
+        "próximamente" | "proximamente" | "upcoming" => Some(AnimeStatus::Upcoming),
+        _ => None,
     }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn episode_count(anime: &JsValue) -> Option<u32> {
+    ["episodesCount", "episodeCount", "totalEpisodes"]
+        .into_iter()
+        .find_map(|key| anime.get(key)?.as_f64())
+        .filter(|count| count.is_finite() && *count >= 1.0)
+        .map(|count| count as u32)
+}
+
+fn parse_episode(episode: &JsValue) -> Option<Episode> {
+    let number = episode.get("number")?.as_f64()?;
+    let title = ["title", "name"]
+        .into_iter()
+        .find_map(|key| episode.get(key)?.as_str())
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .map(ToOwned::to_owned);
+    let thumbnail_url = ["thumbnail", "image", "screenshot"]
+        .into_iter()
+        .find_map(|key| episode.get(key)?.as_str())
+        .map(ToOwned::to_owned);
+    let air_date = ["airDate", "airedAt", "date", "createdAt"]
+        .into_iter()
+        .find_map(|key| episode.get(key)?.as_str())
+        .and_then(|date| date.get(..10))
+        .map(ToOwned::to_owned);
+    let kind = episode_kind(episode, number, title.as_deref());
+
+    Some(Episode {
+        number,
+        title,
+        thumbnail_url,
+        air_date,
+        kind,
+    })
+}
+
+fn episode_kind(episode: &JsValue, number: f64, title: Option<&str>) -> EpisodeKind {
+    let hint = ["type", "kind"]
+        .into_iter()
+        .find_map(|key| episode.get(key)?.as_str())
+        .or(title)
+        .unwrap_or_default()
+        .to_lowercase();
 
-    fn pages() -> usize {
-        (1..=PAGES).cartesian_product(LETTERS.chars()).count()
+    if hint.contains("recap") || hint.contains("resumen") {
+        EpisodeKind::Recap
+    } else if hint
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word == "ova")
+    {
+        EpisodeKind::Ova
+    } else if hint.contains("especial") || hint.contains("special") || number.fract() != 0.0 {
+        EpisodeKind::Special
+    } else {
+        EpisodeKind::Regular
     }
 }
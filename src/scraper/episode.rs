@@ -0,0 +1,71 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum EpisodeKind {
+    #[default]
+    Regular,
+    Special,
+    Recap,
+    Ova,
+}
+
+impl fmt::Display for EpisodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Regular => write!(f, "Episodio"),
+            Self::Special => write!(f, "Especial"),
+            Self::Recap => write!(f, "Resumen"),
+            Self::Ova => write!(f, "OVA"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Episode {
+    pub number: f64,
+    pub title: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub air_date: Option<String>,
+    pub kind: EpisodeKind,
+}
+
+impl fmt::Display for Episode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number)
+    }
+}
+
+impl Episode {
+    pub fn new(number: f64) -> Self {
+        Self {
+            number,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_special(&self) -> bool {
+        self.kind != EpisodeKind::Regular
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = match self.kind {
+            EpisodeKind::Regular => self.number.to_string(),
+            kind => format!("{kind} {}", self.number),
+        };
+
+        if let Some(title) = &self.title {
+            label.push_str(" · ");
+            label.push_str(title);
+        }
+
+        if let Some(air_date) = &self.air_date {
+            label.push_str(" (");
+            label.push_str(air_date);
+            label.push(')');
+        }
+
+        label
+    }
+}
@@ -0,0 +1,67 @@
+use std::{error::Error, fmt};
+
+use reqwest::StatusCode;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScraperError {
+    Network(String),
+    Timeout,
+    Status(u16),
+    Parse(String),
+    NotFound,
+    Blocked,
+}
+
+impl ScraperError {
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::Timeout => true,
+            Self::Status(status) => *status >= 500 || *status == 408 || *status == 429,
+            Self::Parse(_) | Self::NotFound | Self::Blocked => false,
+        }
+    }
+
+    pub fn from_status(status: StatusCode) -> Option<Self> {
+        match status {
+            _ if status.is_success() => None,
+            StatusCode::NOT_FOUND | StatusCode::GONE => Some(Self::NotFound),
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => Some(Self::Blocked),
+            _ => Some(Self::Status(status.as_u16())),
+        }
+    }
+}
+
+impl fmt::Display for ScraperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(err) => write!(f, "Error de red: {err}"),
+            Self::Timeout => write!(f, "Tiempo de espera agotado"),
+            Self::Status(status) => write!(f, "El servidor respondió con el código {status}"),
+            Self::Parse(what) => write!(f, "No se ha podido leer la página ({what})"),
+            Self::NotFound => write!(f, "No encontrado"),
+            Self::Blocked => write!(f, "Acceso bloqueado por el servidor"),
+        }
+    }
+}
+
+impl Error for ScraperError {}
+
+impl From<reqwest::Error> for ScraperError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else if let Some(status) = err.status().and_then(Self::from_status) {
+            status
+        } else if err.is_decode() {
+            Self::Parse(err.to_string())
+        } else {
+            Self::Network(err.to_string())
+        }
+    }
+}
+
+impl From<regex::Error> for ScraperError {
+    fn from(err: regex::Error) -> Self {
+        Self::Parse(err.to_string())
+    }
+}
@@ -0,0 +1,386 @@
+use super::{ScraperResult, error::ScraperError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsValue {
+    Undefined,
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Self>),
+    Object(Vec<(String, Self)>),
+    Identifier(String),
+}
+
+impl JsValue {
+    pub fn find(source: &str, key: &str) -> ScraperResult<Self> {
+        let mut error = None;
+
+        for (start, _) in source.match_indices(key) {
+            let before = source[..start].chars().next_back();
+            if before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$') {
+                continue;
+            }
+
+            let mut parser = Parser {
+                source,
+                pos: start + key.len(),
+            };
+
+            if before == Some('"') && parser.peek() == Some('"') {
+                parser.pos += 1;
+            }
+
+            parser.skip_whitespace();
+            if parser.peek() != Some(':') {
+                continue;
+            }
+            parser.pos += 1;
+
+            match parser.value() {
+                Ok(value) => return Ok(value),
+                Err(err) => error = error.or(Some(err)),
+            }
+        }
+
+        Err(error.unwrap_or_else(|| ScraperError::Parse(format!("{key} not found"))))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> ScraperError {
+        let found = self
+            .peek()
+            .map_or_else(|| "end of input".into(), |c| format!("'{c}'"));
+        ScraperError::Parse(format!("{message}, found {found} at byte {}", self.pos))
+    }
+
+    fn expect(&mut self, expected: char) -> ScraperResult<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{expected}'")))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn value(&mut self) -> ScraperResult<JsValue> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some(quote @ ('"' | '\'' | '`')) => {
+                self.pos += 1;
+                self.string(quote).map(JsValue::String)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let value = self.value()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => self.number(),
+            Some(c) if is_identifier_start(c) => self.keyword(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn object(&mut self) -> ScraperResult<JsValue> {
+        self.expect('{')?;
+        let mut entries = vec![];
+
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(quote @ ('"' | '\'')) => {
+                    self.pos += 1;
+                    self.string(quote)?
+                }
+                Some(c) if is_identifier_start(c) || c.is_ascii_digit() => self.identifier(),
+                _ => return Err(self.error("expected an object key")),
+            };
+
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => {}
+                Some('}') => break,
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+
+        Ok(JsValue::Object(entries))
+    }
+
+    fn array(&mut self) -> ScraperResult<JsValue> {
+        self.expect('[')?;
+        let mut values = vec![];
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    values.push(JsValue::Undefined);
+                    continue;
+                }
+                _ => values.push(self.value()?),
+            }
+
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => break,
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+
+        Ok(JsValue::Array(values))
+    }
+
+    fn string(&mut self, quote: char) -> ScraperResult<String> {
+        let mut string = String::new();
+
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some(c) if c == quote => break,
+                Some('\\') => self.escape(&mut string)?,
+                Some(c) => string.push(c),
+            }
+        }
+
+        Ok(string)
+    }
+
+    fn escape(&mut self, string: &mut String) -> ScraperResult<()> {
+        match self.bump() {
+            Some('n') => string.push('\n'),
+            Some('t') => string.push('\t'),
+            Some('r') => string.push('\r'),
+            Some('b') => string.push('\u{8}'),
+            Some('f') => string.push('\u{c}'),
+            Some('v') => string.push('\u{b}'),
+            Some('0') => string.push('\0'),
+            Some('\n') => {}
+            Some('\r') => {
+                if self.peek() == Some('\n') {
+                    self.pos += 1;
+                }
+            }
+            Some('x') => {
+                let code = self.hex(2)?;
+                string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            Some('u') => {
+                let code = self.unicode()?;
+                string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            Some(c) => string.push(c),
+            None => return Err(self.error("unterminated escape sequence")),
+        }
+
+        Ok(())
+    }
+
+    fn unicode(&mut self) -> ScraperResult<u32> {
+        if self.peek() == Some('{') {
+            self.pos += 1;
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                self.pos += 1;
+            }
+            let code = u32::from_str_radix(&self.source[start..self.pos], 16)
+                .map_err(|_| self.error("invalid unicode escape"))?;
+            self.expect('}')?;
+            return Ok(code);
+        }
+
+        let high = self.hex(4)?;
+        if !(0xD800..0xDC00).contains(&high) || !self.source[self.pos..].starts_with("\\u") {
+            return Ok(high);
+        }
+
+        let checkpoint = self.pos;
+        self.pos += 2;
+        match self.hex(4) {
+            Ok(low) if (0xDC00..0xE000).contains(&low) => {
+                Ok(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            }
+            _ => {
+                self.pos = checkpoint;
+                Ok(high)
+            }
+        }
+    }
+
+    fn hex(&mut self, digits: usize) -> ScraperResult<u32> {
+        let digits = self
+            .source
+            .get(self.pos..self.pos + digits)
+            .filter(|s| s.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid hexadecimal escape"))?;
+        self.pos += digits.len();
+        u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid hexadecimal escape"))
+    }
+
+    fn number(&mut self) -> ScraperResult<JsValue> {
+        let start = self.pos;
+        let negative = match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                true
+            }
+            Some('+') => {
+                self.pos += 1;
+                false
+            }
+            _ => false,
+        };
+
+        if self.peek().is_some_and(is_identifier_start) {
+            return match self.identifier().as_str() {
+                "Infinity" if negative => Ok(JsValue::Number(f64::NEG_INFINITY)),
+                "Infinity" => Ok(JsValue::Number(f64::INFINITY)),
+                _ => {
+                    self.pos = start;
+                    Err(self.error("expected a number"))
+                }
+            };
+        }
+
+        let digits_start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_'))
+            || (matches!(self.peek(), Some('-' | '+'))
+                && matches!(self.source[..self.pos].chars().next_back(), Some('e' | 'E')))
+        {
+            self.pos += 1;
+        }
+
+        let digits = self.source[digits_start..self.pos].replace('_', "");
+        let value = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+            .map_or_else(
+                || digits.trim_end_matches('n').parse::<f64>().ok(),
+                |hex| u32::from_str_radix(hex, 16).ok().map(f64::from),
+            );
+
+        value
+            .map(|n| JsValue::Number(if negative { -n } else { n }))
+            .ok_or_else(|| {
+                self.pos = start;
+                self.error("invalid number")
+            })
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        {
+            self.bump();
+        }
+        self.source[start..self.pos].to_owned()
+    }
+
+    fn keyword(&mut self) -> ScraperResult<JsValue> {
+        let identifier = self.identifier();
+
+        match identifier.as_str() {
+            "true" => Ok(JsValue::Bool(true)),
+            "false" => Ok(JsValue::Bool(false)),
+            "null" => Ok(JsValue::Null),
+            "undefined" => Ok(JsValue::Undefined),
+            "NaN" => Ok(JsValue::Number(f64::NAN)),
+            "Infinity" => Ok(JsValue::Number(f64::INFINITY)),
+            "void" => {
+                self.value()?;
+                Ok(JsValue::Undefined)
+            }
+            "new" => {
+                self.skip_whitespace();
+                self.identifier();
+                self.expect('(')?;
+                self.skip_whitespace();
+                if self.peek() == Some(')') {
+                    self.pos += 1;
+                    return Ok(JsValue::Array(vec![]));
+                }
+                let value = self.value()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            _ => Ok(JsValue::Identifier(identifier)),
+        }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
@@ -0,0 +1,128 @@
+use std::{fmt, str::FromStr};
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, EnumIter, Serialize, Deserialize)]
+pub enum Audio {
+    #[default]
+    Sub,
+    Dub,
+}
+
+impl fmt::Display for Audio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sub => write!(f, "SUB"),
+            Self::Dub => write!(f, "DUB"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseAudioError;
+
+impl FromStr for Audio {
+    type Err = ParseAudioError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "SUB" => Ok(Self::Sub),
+            "DUB" => Ok(Self::Dub),
+            _ => Err(ParseAudioError),
+        }
+    }
+}
+
+impl Audio {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Sub => Self::Dub,
+            Self::Dub => Self::Sub,
+        }
+    }
+
+    pub const fn prev(self) -> Self {
+        self.next()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mirror {
+    pub url: String,
+    pub server: Option<String>,
+    pub audio: Audio,
+}
+
+impl Mirror {
+    pub fn host(&self) -> Option<String> {
+        let host = Url::parse(&self.url).ok()?.host_str()?.to_owned();
+        Some(
+            host.strip_prefix("www.")
+                .map(ToOwned::to_owned)
+                .unwrap_or(host),
+        )
+    }
+}
+
+pub const DEFAULT_HOSTS: [&str; 3] = ["mp4upload", "ok.ru", "my.mail.ru"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorPreference {
+    pub audio: Audio,
+    pub hosts: Vec<String>,
+    pub pinned: Option<String>,
+}
+
+impl Default for MirrorPreference {
+    fn default() -> Self {
+        Self {
+            audio: Audio::default(),
+            hosts: DEFAULT_HOSTS.map(ToOwned::to_owned).to_vec(),
+            pinned: None,
+        }
+    }
+}
+
+impl MirrorPreference {
+    pub fn host_rank(&self, mirror: &Mirror) -> Option<usize> {
+        let host = mirror.host()?;
+        self.hosts
+            .iter()
+            .position(|known| host == *known || host.contains(known.as_str()))
+    }
+
+    pub fn is_pinned(&self, mirror: &Mirror) -> bool {
+        self.pinned.as_ref().is_some_and(|pinned| {
+            mirror
+                .host()
+                .is_some_and(|host| host == *pinned || host.contains(pinned.as_str()))
+        })
+    }
+
+    pub fn sort_key(&self, mirror: &Mirror) -> (bool, bool, usize) {
+        (
+            mirror.audio != self.audio,
+            !self.is_pinned(mirror),
+            self.host_rank(mirror).unwrap_or(usize::MAX),
+        )
+    }
+
+    pub fn rank(&self, mut mirrors: Vec<Mirror>) -> Vec<Mirror> {
+        mirrors.retain(|mirror| self.host_rank(mirror).is_some());
+        mirrors.sort_by_key(|mirror| self.sort_key(mirror));
+        mirrors
+    }
+}
+
+pub fn variants(mirrors: &[Mirror]) -> Vec<Audio> {
+    let mut variants = Vec::new();
+    for mirror in mirrors {
+        if !variants.contains(&mirror.audio) {
+            variants.push(mirror.audio);
+        }
+    }
+    variants.sort_by_key(|audio| *audio == Audio::Dub);
+    variants
+}
@@ -1,107 +1,218 @@
 use std::{
-    error::Error,
     fmt,
     str::FromStr,
-    sync::{Arc, atomic::AtomicUsize},
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
 use anime::Anime;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use strum_macros::EnumIter;
+use episode::Episode;
+use error::ScraperError;
+use iced::futures::future::BoxFuture;
+use itertools::Itertools;
+use mirror::Mirror;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::scraper::animeav1scraper::AnimeAv1Scraper;
+use crate::http_client::HttpClient;
 
 pub mod anime;
 pub mod animeav1scraper;
+pub mod episode;
+pub mod error;
+pub mod js_value;
+pub mod mirror;
+
+pub type ScraperResult<T> = Result<T, ScraperError>;
+
+pub static SCRAPERS: LazyLock<Vec<&'static ScraperEntry>> = LazyLock::new(|| {
+    inventory::iter::<ScraperEntry>
+        .into_iter()
+        .sorted_by_key(|entry| entry.label)
+        .collect()
+});
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParseScraperError;
 
-#[derive(Clone, Debug, EnumIter, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
-pub enum ScraperImpl {
-    #[default]
-    AnimeAv1Scraper,
+#[derive(Debug, Default)]
+pub struct SearchProgress {
+    completed: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl SearchProgress {
+    pub fn complete(&self) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_total(&self, pages: usize) {
+        self.total.fetch_add(pages, Ordering::Relaxed);
+    }
+
+    pub fn completed(&self) -> usize {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    pub progress: bool,
+    pub dub: bool,
+    pub download: bool,
+}
+
+pub struct ScraperEntry {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub capabilities: Capabilities,
+    pub scraper: &'static dyn Scraper,
+}
+
+inventory::collect!(ScraperEntry);
+
+#[derive(Clone, Copy)]
+pub struct ScraperImpl(&'static ScraperEntry);
+
+impl Default for ScraperImpl {
+    fn default() -> Self {
+        Self(SCRAPERS[0])
+    }
+}
+
+impl PartialEq for ScraperImpl {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.name == other.0.name
+    }
+}
+
+impl Eq for ScraperImpl {}
+
+impl fmt::Debug for ScraperImpl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.name)
+    }
 }
 
 impl FromStr for ScraperImpl {
     type Err = ParseScraperError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "AnimeAv1Scraper" => Ok(Self::AnimeAv1Scraper),
-            _ => Err(ParseScraperError {}),
-        }
+        SCRAPERS
+            .iter()
+            .find(|entry| entry.name == s || entry.label == s)
+            .map(|&entry| Self(entry))
+            .ok_or(ParseScraperError)
     }
 }
 
 impl fmt::Display for ScraperImpl {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{self:?}")
+        write!(f, "{}", self.0.label)
+    }
+}
+
+impl Serialize for ScraperImpl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.name)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScraperImpl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| serde::de::Error::custom(format!("unknown scraper \"{name}\"")))
     }
 }
 
 impl ScraperImpl {
-    pub const fn next(self) -> Self {
-        match self {
-            Self::AnimeAv1Scraper => Self::AnimeAv1Scraper,
-        }
+    pub fn iter() -> impl Iterator<Item = Self> {
+        SCRAPERS.iter().map(|&entry| Self(entry))
+    }
+
+    fn index(self) -> usize {
+        SCRAPERS
+            .iter()
+            .position(|entry| entry.name == self.0.name)
+            .unwrap_or_default()
+    }
+
+    pub fn next(self) -> Self {
+        Self(SCRAPERS[(self.index() + 1).min(SCRAPERS.len() - 1)])
     }
 
-    pub const fn prev(self) -> Self {
-        match self {
-            Self::AnimeAv1Scraper => Self::AnimeAv1Scraper,
-        }
+    pub fn prev(self) -> Self {
+        Self(SCRAPERS[self.index().saturating_sub(1)])
+    }
+
+    pub const fn name(self) -> &'static str {
+        self.0.name
+    }
+
+    pub const fn capabilities(self) -> Capabilities {
+        self.0.capabilities
     }
 
     pub async fn try_search(
         &self,
-        client: &Client,
-        progress: Arc<AtomicUsize>,
-    ) -> Result<Vec<Anime>, Box<dyn Error>> {
-        match self {
-            Self::AnimeAv1Scraper => AnimeAv1Scraper::try_search(client, progress).await,
-        }
+        client: &HttpClient,
+        progress: Arc<SearchProgress>,
+    ) -> ScraperResult<Vec<Anime>> {
+        self.0.scraper.try_search(client, progress).await
     }
 
     pub async fn try_get_episodes(
         &self,
-        client: &Client,
+        client: &HttpClient,
         slug: &str,
-    ) -> Result<Vec<f64>, Box<dyn Error>> {
-        match self {
-            Self::AnimeAv1Scraper => AnimeAv1Scraper::try_get_episodes(client, slug).await,
-        }
+    ) -> ScraperResult<Vec<Episode>> {
+        self.0.scraper.try_get_episodes(client, slug).await
     }
 
     pub async fn try_get_mirrors(
         &self,
-        client: &Client,
+        client: &HttpClient,
         slug: &str,
         episode: f64,
-    ) -> Result<Vec<String>, Box<dyn Error>> {
-        match self {
-            Self::AnimeAv1Scraper => AnimeAv1Scraper::try_get_mirrors(client, slug, episode).await,
-        }
-    }
-
-    pub fn pages(self) -> usize {
-        match self {
-            Self::AnimeAv1Scraper => AnimeAv1Scraper::pages(),
-        }
+    ) -> ScraperResult<Vec<Mirror>> {
+        self.0.scraper.try_get_mirrors(client, slug, episode).await
     }
 }
 
-pub trait Scraper {
-    async fn try_search(
-        client: &Client,
-        progress: Arc<AtomicUsize>,
-    ) -> Result<Vec<Anime>, Box<dyn Error>>;
-    async fn try_get_episodes(client: &Client, slug: &str) -> Result<Vec<f64>, Box<dyn Error>>;
-    async fn try_get_mirrors(
-        client: &Client,
-        slug: &str,
+pub trait Scraper: Send + Sync {
+    fn try_search<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        progress: Arc<SearchProgress>,
+    ) -> BoxFuture<'a, ScraperResult<Vec<Anime>>>;
+    fn try_get_episodes<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        slug: &'a str,
+    ) -> BoxFuture<'a, ScraperResult<Vec<Episode>>>;
+    fn try_get_mirrors<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        slug: &'a str,
         episode: f64,
-    ) -> Result<Vec<String>, Box<dyn Error>>;
-    fn pages() -> usize;
+    ) -> BoxFuture<'a, ScraperResult<Vec<Mirror>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_scrapers() {
+        let scraper = "AnimeAv1Scraper".parse::<ScraperImpl>().unwrap();
+
+        assert_eq!(scraper, ScraperImpl::default());
+        assert!(ScraperImpl::iter().any(|entry| entry == scraper));
+    }
 }
@@ -17,31 +17,32 @@ use iced::{
     },
 };
 use itertools::Itertools;
-use notify_rust::Notification;
 use rayon::prelude::*;
-use reqwest::Client;
 use rust_fuzzy_search::fuzzy_compare;
 use std::{
+    collections::HashMap,
     env::temp_dir,
     fs::{create_dir_all, write},
     mem,
     process::{Command, Stdio},
-    sync::{Arc, atomic::AtomicUsize},
+    sync::Arc,
     time::Duration,
 };
 use tokio::runtime::Handle;
 
 use crate::{
-    app,
+    app, catalogue_cache,
     config::Config,
-    download::DownloadToken,
-    episodes_page::{EpisodesPage, WHITELIST},
+    download::{Download, DownloadToken},
+    episodes_page::EpisodesPage,
+    http_client::HttpClient,
     image_query_state::ImageQueryState,
     list_query_state::ListQueryState,
     main_menu_page::{MainMenuPage, Selection},
+    media_server, notifications,
     page::{AppUpdate, Page},
     presets::{highlight, square_box, transparent_button_cond},
-    scraper::anime::Anime,
+    scraper::{ScraperResult, anime::Anime, episode::Episode},
 };
 
 pub const SEARCH_BAR_ID: &str = "search_bar";
@@ -53,22 +54,25 @@ pub enum Message {
     Click(usize),
     Submit,
     KeyPressed(Key),
-    Retrieved(Anime, Vec<f64>),
+    Retrieved(Anime, ScraperResult<Vec<Episode>>),
 }
 
 pub struct SearchPage {
     pub config: Config,
-    pub client: Client,
+    pub client: HttpClient,
     pub anime_list: Vec<Anime>,
     pub query: String,
     pub selected: usize,
     pub filtered_list: Vec<Anime>,
     pub image: ImageQueryState,
+    pub refresh: ListQueryState,
+    pub download: Arc<Download>,
 }
 
 impl Page for SearchPage {
     #[allow(clippy::too_many_lines)]
     fn view(&self) -> iced::Element<'_, crate::app::Message> {
+        let download = self.config.scraper.capabilities().download;
         let selected = self.selected;
         let anime = &self.filtered_list[self.selected];
         column![
@@ -97,7 +101,7 @@ impl Page for SearchPage {
                         container(
                             Scrollable::new(Column::with_children(
                                 self.filtered_list.iter().enumerate().map(|(i, anime)| {
-                                    let name = anime.names[0].clone();
+                                    let name = anime.title.clone();
                                     Element::new(
                                         transparent_button_cond(&name, || selected == i)
                                             .on_press(app::Message::Search(Message::Click(i))),
@@ -125,8 +129,10 @@ impl Page for SearchPage {
                                 span(" → L Enter ").color(self.config.theme().palette().primary),
                                 span(" Buscar:").color(self.config.theme().palette().text),
                                 span(" F / ").color(self.config.theme().palette().primary),
-                                span(" Descargar:").color(self.config.theme().palette().text),
-                                span(" D ").color(self.config.theme().palette().primary),
+                                span(if download { " Descargar:" } else { "" })
+                                    .color(self.config.theme().palette().text),
+                                span(if download { " D " } else { "" })
+                                    .color(self.config.theme().palette().primary),
                                 span(" Syncplay:").color(self.config.theme().palette().text),
                                 span(" S ").color(self.config.theme().palette().primary),
                                 span(" Salir:").color(self.config.theme().palette().text),
@@ -148,7 +154,7 @@ impl Page for SearchPage {
                             column![
                                 column![
                                     image(handle).width(Length::Fill),
-                                    text(&anime.names[0])
+                                    text(&anime.title)
                                         .font(Font {
                                             weight: iced::font::Weight::Bold,
                                             ..Font::DEFAULT
@@ -160,6 +166,15 @@ impl Page for SearchPage {
                                         .align_x(Horizontal::Center)
                                         .align_y(Vertical::Bottom)
                                 ],
+                                text(anime.details())
+                                    .style(|theme: &iced::Theme| text::Style {
+                                        color: Some(highlight(theme.palette().text, 40.0))
+                                    })
+                                    .width(Length::Fill)
+                                    .align_x(Horizontal::Center),
+                                text(anime.genres.join(", "))
+                                    .width(Length::Fill)
+                                    .align_x(Horizontal::Center),
                                 text(&anime.synopsis).width(Length::Fill)
                             ]
                             .spacing(6)
@@ -184,16 +199,39 @@ impl Page for SearchPage {
     fn update(&mut self, message: crate::app::Message) -> AppUpdate {
         if let app::Message::Search(message) = message {
             match message {
-                Message::Retrieved(anime, episodes) => AppUpdate::Page(Box::new(EpisodesPage {
-                    config: mem::take(&mut self.config),
-                    client: mem::take(&mut self.client),
-                    search_query: mem::take(&mut self.query),
-                    search_selected: self.selected,
-                    selected: 0,
-                    anime_list: mem::take(&mut self.anime_list),
-                    anime,
-                    episodes,
-                })),
+                Message::Retrieved(anime, Err(err)) => {
+                    notifications::notify(
+                        &anime.slug,
+                        &format!("No se han podido obtener los episodios de {anime}: {err}"),
+                        vec![],
+                    );
+                    AppUpdate::None
+                }
+                Message::Retrieved(anime, Ok(mut episodes)) => {
+                    episodes.sort_by_key(Episode::is_special);
+                    self.download
+                        .library()
+                        .scan(&self.config, &anime, &episodes);
+
+                    AppUpdate::Page(Box::new(EpisodesPage {
+                        config: mem::take(&mut self.config),
+                        client: mem::take(&mut self.client),
+                        search_query: mem::take(&mut self.query),
+                        search_selected: self.selected,
+                        selected: 0,
+                        anime_list: mem::take(&mut self.anime_list),
+                        anime,
+                        episodes,
+                        variants: HashMap::new(),
+                        probe_failures: HashMap::new(),
+                        probing: false,
+                        picker: None,
+                        visual: None,
+                        batch: None,
+                        refresh: mem::take(&mut self.refresh),
+                        download: self.download.clone(),
+                    }))
+                }
                 Message::Update(text) => {
                     self.query = text;
                     self.fuzzy();
@@ -203,47 +241,17 @@ impl Page for SearchPage {
                 Message::Click(index) => {
                     if self.selected != index {
                         self.selected = index;
-                        self.image = ImageQueryState::spawn(
-                            self.client.clone(),
-                            self.filtered_list
-                                .get(index)
-                                .expect("No animes found")
-                                .image_url
-                                .clone(),
-                        );
+                        self.image = self.image_at(index);
                         return AppUpdate::None;
                     }
 
-                    let anime = self.filtered_list[self.selected].clone();
-                    let scraper = self.config.scraper;
-                    let client = self.client.clone();
-
-                    AppUpdate::Task(Task::perform(
-                        async move {
-                            let episodes = scraper
-                                .try_get_episodes(&client, &anime.names[1])
-                                .await
-                                .unwrap();
-
-                            (anime.clone(), episodes)
-                        },
-                        |(anime, episodes)| {
-                            app::Message::Search(Message::Retrieved(anime, episodes))
-                        },
-                    ))
+                    AppUpdate::Task(self.retrieve_episodes())
                 }
                 Message::KeyPressed(key) => match key.as_ref() {
                     Key::Character("j") | Key::Named(ArrowDown) => {
                         if self.selected < self.filtered_list.len() - 1 {
                             self.selected += 1;
-                            self.image = ImageQueryState::spawn(
-                                self.client.clone(),
-                                self.filtered_list
-                                    .get(self.selected)
-                                    .expect("No animes found")
-                                    .image_url
-                                    .clone(),
-                            );
+                            self.image = self.image_at(self.selected);
                             return AppUpdate::Task(self.scroll_to_index());
                         }
                         AppUpdate::None
@@ -251,36 +259,13 @@ impl Page for SearchPage {
                     Key::Character("k") | Key::Named(ArrowUp) => {
                         if self.selected > 0 {
                             self.selected -= 1;
-                            self.image = ImageQueryState::spawn(
-                                self.client.clone(),
-                                self.filtered_list
-                                    .get(self.selected)
-                                    .expect("No animes found")
-                                    .image_url
-                                    .clone(),
-                            );
+                            self.image = self.image_at(self.selected);
                             return AppUpdate::Task(self.scroll_to_index());
                         }
                         AppUpdate::None
                     }
                     Key::Character("l") | Key::Named(ArrowRight | Enter) => {
-                        let anime = self.filtered_list[self.selected].clone();
-                        let scraper = self.config.scraper;
-                        let client = self.client.clone();
-
-                        AppUpdate::Task(Task::perform(
-                            async move {
-                                let episodes = scraper
-                                    .try_get_episodes(&client, &anime.names[1])
-                                    .await
-                                    .unwrap();
-
-                                (anime.clone(), episodes)
-                            },
-                            |(anime, episodes)| {
-                                app::Message::Search(Message::Retrieved(anime, episodes))
-                            },
-                        ))
+                        AppUpdate::Task(self.retrieve_episodes())
                     }
                     Key::Character("f" | "/") => {
                         self.selected = 0;
@@ -289,37 +274,57 @@ impl Page for SearchPage {
                             self.scroll_to_index(),
                         ]))
                     }
-                    Key::Character("d") => {
+                    Key::Character("d") if self.config.scraper.capabilities().download => {
                         let anime = self.filtered_list[self.selected].clone();
-                        let episodes = Handle::current()
-                            .block_on(
-                                self.config
-                                    .scraper
-                                    .try_get_episodes(&self.client, &anime.names[1]),
-                            )
-                            .expect("Couldn't get episodes");
-
-                        let _ = Notification::new()
-                            .summary("Ani-link")
-                            .body(
-                                format!(
-                                    r"Añadiendo {} episodios de {} a la cola de descargas...",
-                                    episodes.len(),
-                                    anime.names[0]
-                                )
-                                .as_str(),
-                            )
-                            .show()
-                            .is_ok();
+                        let preference = self.config.mirror_preference(&anime.slug);
+                        let episodes = match Handle::current().block_on(
+                            self.config
+                                .scraper
+                                .try_get_episodes(&self.client, &anime.slug),
+                        ) {
+                            Ok(episodes) => episodes,
+                            Err(err) => {
+                                notifications::notify(
+                                    &anime.slug,
+                                    &format!(
+                                        "No se han podido obtener los episodios de {anime}: {err}"
+                                    ),
+                                    vec![],
+                                );
+                                return AppUpdate::None;
+                            }
+                        };
+
+                        let mut library = self.download.library();
+                        library.scan(&self.config, &anime, &episodes);
+                        let episodes = media_server::numbered(episodes)
+                            .into_iter()
+                            .filter(|(episode, _)| {
+                                library.get(&self.config, &anime.slug, episode).is_none()
+                            })
+                            .collect_vec();
+                        drop(library);
+
+                        notifications::notify(
+                            &anime.slug,
+                            &format!(
+                                "Añadiendo {} episodios de {} a la cola de descargas...",
+                                episodes.len(),
+                                anime.title
+                            ),
+                            vec![],
+                        );
 
                         AppUpdate::Task(Task::perform(
                             async move {
                                 episodes
                                     .into_iter()
-                                    .map(|episode| DownloadToken {
-                                        name: anime.names[0].clone(),
-                                        slug: anime.names[1].clone(),
+                                    .map(|(episode, special_index)| DownloadToken {
+                                        anime: anime.clone(),
                                         episode,
+                                        special_index,
+                                        preference: preference.clone(),
+                                        post_profile: None,
                                     })
                                     .collect_vec()
                             },
@@ -332,13 +337,15 @@ impl Page for SearchPage {
                     }
                     Key::Character("q" | "h") | Key::Named(Escape | ArrowLeft) => {
                         AppUpdate::Page(Box::new(MainMenuPage {
+                            catalogue_updated: catalogue_cache::updated(self.config.scraper),
                             config: mem::take(&mut self.config),
                             client: mem::take(&mut self.client),
                             selection: Selection::Search,
-                            anime_list: ListQueryState::Obtained(
+                            anime_list: ListQueryState::join(
                                 mem::take(&mut self.anime_list),
-                                Arc::new(AtomicUsize::new(self.config.scraper.pages())),
+                                mem::take(&mut self.refresh),
                             ),
+                            download: self.download.clone(),
                             waiting: false,
                         }))
                     }
@@ -347,6 +354,16 @@ impl Page for SearchPage {
             }
         } else if matches!(message, app::Message::Update) {
             self.image = mem::take(&mut self.image).get();
+
+            if matches!(self.refresh, ListQueryState::Obtaining(..))
+                && self.refresh.is_ready()
+                && let ListQueryState::Obtained(anime_list, _) = mem::take(&mut self.refresh).get()
+            {
+                self.selected = self.selected.min(anime_list.len().saturating_sub(1));
+                self.anime_list = anime_list;
+                self.fuzzy();
+            }
+
             AppUpdate::None
         } else {
             AppUpdate::None
@@ -371,6 +388,20 @@ impl Page for SearchPage {
 }
 
 impl SearchPage {
+    fn retrieve_episodes(&self) -> Task<app::Message> {
+        let anime = self.filtered_list[self.selected].clone();
+        let scraper = self.config.scraper;
+        let client = self.client.clone();
+
+        Task::perform(
+            async move {
+                let episodes = scraper.try_get_episodes(&client, &anime.slug).await;
+                (anime, episodes)
+            },
+            |(anime, episodes)| app::Message::Search(Message::Retrieved(anime, episodes)),
+        )
+    }
+
     fn scroll_to_index(&self) -> Task<app::Message> {
         let list_len = self.filtered_list.len();
 
@@ -390,6 +421,14 @@ impl SearchPage {
         )
     }
 
+    fn image_at(&self, index: usize) -> ImageQueryState {
+        self.filtered_list
+            .get(index)
+            .map_or_else(ImageQueryState::default, |anime| {
+                ImageQueryState::spawn(self.client.clone(), anime.image_url.clone())
+            })
+    }
+
     pub fn fuzzy(&mut self) {
         let mut result = self
             .anime_list
@@ -397,9 +436,7 @@ impl SearchPage {
             .into_par_iter()
             .filter_map(|anime| {
                 anime
-                    .names
-                    .clone()
-                    .into_par_iter()
+                    .titles()
                     .map(|name| {
                         let name = name.to_lowercase();
                         let score = fuzzy_compare(&self.query, &name);
@@ -419,38 +456,45 @@ impl SearchPage {
         });
 
         self.filtered_list = result.into_iter().map(|(anime, _)| anime).collect();
-        self.image = ImageQueryState::spawn(
-            self.client.clone(),
-            self.filtered_list
-                .get(self.selected)
-                .expect("No animes found")
-                .image_url
-                .clone(),
-        );
+        self.image = self.image_at(self.selected);
     }
 
     fn stream_anime(&self) {
         let anime = &self.filtered_list[self.selected];
-        let viewable = Handle::current()
-            .block_on(
-                self.config
-                    .scraper
-                    .try_get_episodes(&self.client, &anime.names[1]),
-            )
-            .expect("Couldn't get episodes")
+        let preference = self.config.mirror_preference(&anime.slug);
+        let episodes = match Handle::current().block_on(
+            self.config
+                .scraper
+                .try_get_episodes(&self.client, &anime.slug),
+        ) {
+            Ok(episodes) => episodes,
+            Err(err) => {
+                notifications::notify(
+                    &anime.slug,
+                    &format!("No se han podido obtener los episodios de {anime}: {err}"),
+                    vec![],
+                );
+                return;
+            }
+        };
+
+        let viewable = episodes
             .iter()
-            .flat_map(|&episode| {
+            .filter_map(|episode| {
                 Handle::current()
                     .block_on(self.config.scraper.try_get_mirrors(
                         &self.client,
-                        &anime.names[1],
-                        episode,
+                        &anime.slug,
+                        episode.number,
                     ))
-                    .expect("Couldn't get mirrors")
-                    .iter()
-                    .filter(|mirror| WHITELIST.iter().any(|elem| mirror.contains(elem)))
-                    .map(ToOwned::to_owned)
-                    .collect_vec()
+                    .inspect_err(|err| {
+                        eprintln!("Couldn't get the mirrors of episode {episode}: {err}");
+                    })
+                    .ok()
+                    .map(|mirrors| preference.rank(mirrors))?
+                    .into_iter()
+                    .next()
+                    .map(|mirror| mirror.url)
             })
             .join("\n");
 
@@ -480,11 +524,11 @@ impl SearchPage {
             .is_ok();
 
         if !success {
-            let _ = Notification::new()
-                .summary("Ani-link")
-                .body("No se ha podido abrir syncplay")
-                .show()
-                .is_ok();
+            notifications::notify(
+                notifications::PLAYER,
+                "No se ha podido abrir syncplay",
+                vec![],
+            );
         }
     }
 }
@@ -0,0 +1,355 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{LazyLock, Mutex},
+};
+
+use dirs::{config_dir, state_dir};
+
+use crate::{
+    atomic_file,
+    config::Config,
+    download::{self, format_bytes},
+    history,
+};
+
+pub const EPISODE_SIZE_ESTIMATE: u64 = 300 * 1024 * 1024;
+const GIB: u64 = 1024 * 1024 * 1024;
+
+static SIZES: LazyLock<Mutex<HashMap<PathBuf, u64>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone)]
+pub struct Usage {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+pub fn usage(root: &Path) -> Vec<Usage> {
+    let mut usage = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| Usage {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            size: size(&entry.path()),
+            path: entry.path(),
+        })
+        .collect::<Vec<_>>();
+
+    usage.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+    let mut sizes = SIZES.lock().expect("Couldn't lock mutex");
+    sizes.retain(|path, _| !path.starts_with(root));
+    sizes.insert(
+        root.to_path_buf(),
+        usage.iter().map(|entry| entry.size).sum(),
+    );
+    sizes.extend(usage.iter().map(|entry| (entry.path.clone(), entry.size)));
+    drop(sizes);
+
+    usage
+}
+
+pub fn size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+
+    if metadata.is_dir() {
+        fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| size(&entry.path()))
+            .sum()
+    } else {
+        metadata.len()
+    }
+}
+
+fn cached_size(path: &Path) -> u64 {
+    if let Some(size) = SIZES.lock().expect("Couldn't lock mutex").get(path) {
+        return *size;
+    }
+
+    let size = size(path);
+    SIZES
+        .lock()
+        .expect("Couldn't lock mutex")
+        .insert(path.to_path_buf(), size);
+    size
+}
+
+pub fn add_usage(file: &Path, bytes: u64) {
+    for (path, size) in SIZES.lock().expect("Couldn't lock mutex").iter_mut() {
+        if file.starts_with(path) {
+            *size = size.saturating_add(bytes);
+        }
+    }
+}
+
+fn remove_usage(removed: &Path, bytes: u64) {
+    SIZES
+        .lock()
+        .expect("Couldn't lock mutex")
+        .retain(|path, size| {
+            if removed.starts_with(path) {
+                *size = size.saturating_sub(bytes);
+            }
+            !path.starts_with(removed)
+        });
+}
+
+fn forget_usage(changed: &Path) {
+    SIZES
+        .lock()
+        .expect("Couldn't lock mutex")
+        .retain(|path, _| !changed.starts_with(path) && !path.starts_with(changed));
+}
+
+pub fn anime_dir(root: &Path, stem: &Path) -> Option<PathBuf> {
+    let mut components = stem.strip_prefix(root).ok()?.components();
+    let first = components.next()?;
+    components.next()?;
+    Some(root.join(first))
+}
+
+pub fn preflight(config: &Config, stem: &Path) -> Result<(), String> {
+    let root = &config.download_dir;
+
+    if let Some(free) = free_space(root)
+        && free < EPISODE_SIZE_ESTIMATE
+    {
+        return Err(format!(
+            "no hay espacio suficiente en el disco ({} libres)",
+            format_bytes(free)
+        ));
+    }
+
+    if config.storage_quota_gib > 0 {
+        let quota = config.storage_quota_gib.saturating_mul(GIB);
+        if cached_size(root) + EPISODE_SIZE_ESTIMATE > quota {
+            return Err(format!(
+                "se ha alcanzado la cuota de almacenamiento ({})",
+                format_bytes(quota)
+            ));
+        }
+    }
+
+    if config.anime_quota_gib > 0
+        && let Some(dir) = anime_dir(root, stem)
+    {
+        let quota = config.anime_quota_gib.saturating_mul(GIB);
+        if cached_size(&dir) + EPISODE_SIZE_ESTIMATE > quota {
+            return Err(format!(
+                "se ha alcanzado la cuota de almacenamiento de este anime ({})",
+                format_bytes(quota)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn free_space(path: &Path) -> Option<u64> {
+    available(path.ancestors().find(|path| path.exists())?)
+}
+
+#[cfg(unix)]
+fn available(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+
+    #[allow(clippy::unnecessary_cast)]
+    Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+#[cfg(windows)]
+fn available(path: &Path) -> Option<u64> {
+    use std::{os::windows::ffi::OsStrExt, ptr};
+
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path = path
+        .as_os_str()
+        .encode_wide()
+        .chain([0])
+        .collect::<Vec<_>>();
+    let mut free = 0;
+
+    (unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut free, ptr::null_mut(), ptr::null_mut()) }
+        != 0)
+        .then_some(free)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn available(_: &Path) -> Option<u64> {
+    None
+}
+
+pub fn expired(watched: &HashMap<PathBuf, i64>, days: u64, now: i64) -> Vec<PathBuf> {
+    let age = i64::try_from(days.saturating_mul(24 * 60 * 60)).unwrap_or(i64::MAX);
+
+    watched
+        .iter()
+        .filter(|(_, marked)| now.saturating_sub(**marked) >= age)
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+pub fn remove(root: &Path, path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        let bytes = size(path);
+        if let Err(err) = fs::remove_dir_all(path) {
+            forget_usage(path);
+            return Err(err);
+        }
+        remove_usage(path, bytes);
+    } else {
+        let stem = path.with_extension("");
+        let parent = path.parent().unwrap_or(root);
+
+        for entry in fs::read_dir(parent)?.flatten() {
+            let name = entry.file_name();
+            if download::stem_extension(&stem, &name.to_string_lossy()).is_some() {
+                let bytes = size(&entry.path());
+                fs::remove_file(entry.path())?;
+                remove_usage(&entry.path(), bytes);
+            }
+        }
+    }
+
+    for dir in path.ancestors().skip(1) {
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn open_folder(path: &Path) -> io::Result<()> {
+    let opener = if cfg!(target_os = "windows") {
+        "explorer.exe"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    Command::new(opener)
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+fn watched_path() -> Option<PathBuf> {
+    let mut path = state_dir().or_else(config_dir)?;
+    path.push("ani-link");
+    path.push("watched.json");
+    Some(path)
+}
+
+pub fn load_watched() -> HashMap<PathBuf, i64> {
+    watched_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|watched| parse_watched(&watched, history::now()))
+        .unwrap_or_default()
+}
+
+fn parse_watched(watched: &str, now: i64) -> Option<HashMap<PathBuf, i64>> {
+    serde_json::from_str(watched).ok().or_else(|| {
+        serde_json::from_str::<Vec<PathBuf>>(watched)
+            .ok()
+            .map(|watched| watched.into_iter().map(|path| (path, now)).collect())
+    })
+}
+
+pub fn save_watched(watched: &HashMap<PathBuf, i64>) -> Result<(), Box<dyn Error>> {
+    let path = watched_path().ok_or("State path not found")?;
+    fs::create_dir_all(path.parent().ok_or("State path has no parent")?)?;
+
+    atomic_file::write_json(&path, watched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_episodes_by_the_time_they_were_watched() {
+        let day = 24 * 60 * 60;
+        let now = 100 * day;
+        let watched = HashMap::from([
+            (PathBuf::from("/videos/old"), now - 8 * day),
+            (PathBuf::from("/videos/recent"), now - day),
+        ]);
+
+        assert_eq!(expired(&watched, 7, now), [PathBuf::from("/videos/old")]);
+    }
+
+    #[test]
+    fn migrates_the_watched_list() {
+        assert_eq!(
+            parse_watched(r#"["/videos/a"]"#, 42),
+            Some(HashMap::from([(PathBuf::from("/videos/a"), 42)]))
+        );
+        assert_eq!(
+            parse_watched(r#"{"/videos/a":7}"#, 42),
+            Some(HashMap::from([(PathBuf::from("/videos/a"), 7)]))
+        );
+    }
+
+    #[test]
+    fn keeps_cached_usage_up_to_date() {
+        let root = std::env::temp_dir().join(format!("ani-link-usage-{}", std::process::id()));
+        let anime = root.join("Anime");
+        fs::create_dir_all(&anime).unwrap();
+        fs::write(anime.join("1.mp4"), [0; 10]).unwrap();
+
+        assert_eq!(cached_size(&root), 10);
+        assert_eq!(cached_size(&anime), 10);
+
+        fs::write(anime.join("2.mp4"), [0; 5]).unwrap();
+        add_usage(&anime.join("2.mp4"), 5);
+        assert_eq!(cached_size(&root), 15);
+
+        remove(&root, &anime.join("1.mp4")).unwrap();
+        assert_eq!(cached_size(&root), 5);
+        assert_eq!(cached_size(&anime), 5);
+
+        remove(&root, &anime).unwrap();
+        assert_eq!(cached_size(&root), 0);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn groups_files_by_their_top_level_folder() {
+        let root = Path::new("/videos/ani-link");
+
+        assert_eq!(
+            anime_dir(root, &root.join("kaguya-sama/kaguya-sama-1")),
+            Some(root.join("kaguya-sama"))
+        );
+        assert_eq!(
+            anime_dir(root, &root.join("Kaguya/Season 01/Kaguya - S01E01")),
+            Some(root.join("Kaguya"))
+        );
+        assert_eq!(anime_dir(root, &root.join("kaguya-sama-1")), None);
+        assert_eq!(anime_dir(root, Path::new("/elsewhere/file")), None);
+    }
+}
@@ -0,0 +1,366 @@
+use std::{collections::HashSet, fmt::Write as _, mem, sync::Arc};
+
+use iced::{
+    Element, Event, Length, Padding, Task,
+    alignment::Horizontal,
+    event::{self, Status},
+    keyboard::{
+        Event::KeyPressed,
+        Key,
+        key::Named::{ArrowDown, ArrowLeft, ArrowUp, Enter, Escape, Space},
+    },
+    never,
+    widget::{
+        Column, Id, Scrollable, column, container,
+        operation::snap_to,
+        rich_text,
+        scrollable::{self, Direction, Scrollbar},
+        span, text,
+    },
+};
+
+use crate::{
+    app, catalogue_cache,
+    config::Config,
+    download::{Download, format_bytes},
+    http_client::HttpClient,
+    list_query_state::ListQueryState,
+    main_menu_page::{self, MainMenuPage},
+    notifications,
+    page::{AppUpdate, Page},
+    presets::{square_box, transparent_button_cond},
+    storage::{self, Usage},
+};
+
+const STORAGE_SCROLLABLE_ID: &str = "storage_scrollable";
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Scanned(Vec<Usage>),
+    Removed,
+    Click(usize),
+    KeyPressed(Key),
+}
+
+pub struct StoragePage {
+    pub config: Config,
+    pub client: HttpClient,
+    pub anime_list: ListQueryState,
+    pub download: Arc<Download>,
+    pub usage: Option<Vec<Usage>>,
+    pub selected: usize,
+    pub marked: HashSet<usize>,
+    pub confirm: bool,
+}
+
+impl Page for StoragePage {
+    fn view(&self) -> iced::Element<'_, app::Message> {
+        let selected = self.selected;
+        let palette = self.config.theme().palette();
+
+        let (list, summary): (Element<'_, app::Message>, String) = match &self.usage {
+            None => (
+                text("Calculando...")
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .into(),
+                String::new(),
+            ),
+            Some(usage) if usage.is_empty() => (
+                text("No hay descargas")
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .into(),
+                String::new(),
+            ),
+            Some(usage) => (
+                Column::with_children(usage.iter().enumerate().map(|(i, entry)| {
+                    let mark = if self.marked.contains(&i) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+
+                    Element::new(
+                        transparent_button_cond(
+                            &format!("{mark} {} - {}", entry.name, format_bytes(entry.size)),
+                            || selected == i,
+                        )
+                        .on_press(app::Message::Storage(Message::Click(i))),
+                    )
+                }))
+                .into(),
+                self.summary(usage),
+            ),
+        };
+
+        let footer = if self.confirm {
+            rich_text![
+                span(format!(
+                    "¿Borrar {} elementos ({})?",
+                    self.targets().len(),
+                    format_bytes(self.targets().iter().map(|entry| entry.size).sum())
+                ))
+                .color(palette.danger),
+                span(" Confirmar:").color(palette.text),
+                span(" Enter Y ").color(palette.primary),
+                span(" Cancelar:").color(palette.text),
+                span(" Esc N").color(palette.primary),
+            ]
+        } else {
+            rich_text![
+                span("Subir:").color(palette.text),
+                span(" ↑ K ").color(palette.primary),
+                span(" Bajar:").color(palette.text),
+                span(" ↓ J ").color(palette.primary),
+                span(" Marcar:").color(palette.text),
+                span(" Espacio X ").color(palette.primary),
+                span(" Marcar todo:").color(palette.text),
+                span(" A ").color(palette.primary),
+                span(" Borrar:").color(palette.text),
+                span(" D ").color(palette.primary),
+                span(" Salir:").color(palette.text),
+                span(" ← H Esc Q").color(palette.primary),
+            ]
+        };
+
+        square_box(
+            column![
+                text("Almacenamiento")
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center),
+                text(summary)
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center),
+                container(
+                    Scrollable::new(list)
+                        .id(Id::new(STORAGE_SCROLLABLE_ID))
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .direction(Direction::Vertical(Scrollbar::new()))
+                )
+                .padding(Padding {
+                    top: 6.0,
+                    right: 6.0,
+                    bottom: 3.0,
+                    left: 6.0
+                }),
+                container(footer.on_link_click(never))
+                    .align_x(Horizontal::Center)
+                    .width(Length::Fill),
+            ]
+            .spacing(3)
+            .padding(3),
+        )
+        .into()
+    }
+
+    fn update(&mut self, message: app::Message) -> AppUpdate {
+        let app::Message::Storage(message) = message else {
+            return AppUpdate::None;
+        };
+
+        match message {
+            Message::Scanned(usage) => {
+                self.selected = self.selected.min(usage.len().saturating_sub(1));
+                self.marked.clear();
+                self.usage = Some(usage);
+                AppUpdate::None
+            }
+            Message::Removed => AppUpdate::Task(self.scan()),
+            Message::Click(index) => {
+                self.selected = index;
+                AppUpdate::None
+            }
+            Message::KeyPressed(key) if self.confirm => match key.as_ref() {
+                Key::Character("y") | Key::Named(Enter) => {
+                    self.confirm = false;
+                    let task = self.remove_targets();
+                    self.usage = None;
+                    AppUpdate::Task(task)
+                }
+                Key::Character("n" | "q") | Key::Named(Escape) => {
+                    self.confirm = false;
+                    AppUpdate::None
+                }
+                _ => AppUpdate::None,
+            },
+            Message::KeyPressed(key) => match key.as_ref() {
+                Key::Character("j") | Key::Named(ArrowDown) => {
+                    if self.selected + 1 < self.len() {
+                        self.selected += 1;
+                        return AppUpdate::Task(self.scroll_to_index());
+                    }
+                    AppUpdate::None
+                }
+                Key::Character("k") | Key::Named(ArrowUp) => {
+                    if self.selected > 0 {
+                        self.selected -= 1;
+                        return AppUpdate::Task(self.scroll_to_index());
+                    }
+                    AppUpdate::None
+                }
+                Key::Character("x") | Key::Named(Space) => {
+                    if self.selected < self.len() && !self.marked.remove(&self.selected) {
+                        self.marked.insert(self.selected);
+                    }
+                    AppUpdate::None
+                }
+                Key::Character("a") => {
+                    if self.marked.len() == self.len() {
+                        self.marked.clear();
+                    } else {
+                        self.marked = (0..self.len()).collect();
+                    }
+                    AppUpdate::None
+                }
+                Key::Character("d") => {
+                    self.confirm = !self.targets().is_empty();
+                    AppUpdate::None
+                }
+                Key::Character("q" | "h") | Key::Named(ArrowLeft | Escape) => {
+                    AppUpdate::Page(Box::new(MainMenuPage {
+                        catalogue_updated: catalogue_cache::updated(self.config.scraper),
+                        config: mem::take(&mut self.config),
+                        client: mem::take(&mut self.client),
+                        selection: main_menu_page::Selection::Storage,
+                        anime_list: mem::take(&mut self.anime_list),
+                        download: self.download.clone(),
+                        waiting: false,
+                    }))
+                }
+                _ => AppUpdate::None,
+            },
+        }
+    }
+
+    fn subscription(&self) -> iced::Subscription<app::Message> {
+        event::listen_with(move |event, status, _| match (event, status) {
+            (Event::Keyboard(KeyPressed { key, .. }), Status::Ignored) => {
+                Some(app::Message::Storage(Message::KeyPressed(key)))
+            }
+            _ => None,
+        })
+    }
+
+    fn theme(&self) -> iced::Theme {
+        self.config.theme()
+    }
+}
+
+impl StoragePage {
+    pub fn scan(&self) -> Task<app::Message> {
+        let root = self.config.download_dir.clone();
+
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || storage::usage(&root))
+                    .await
+                    .unwrap_or_default()
+            },
+            |usage| app::Message::Storage(Message::Scanned(usage)),
+        )
+    }
+
+    fn len(&self) -> usize {
+        self.usage.as_ref().map_or(0, Vec::len)
+    }
+
+    fn summary(&self, usage: &[Usage]) -> String {
+        let total = usage.iter().map(|entry| entry.size).sum();
+        let mut summary = format!("Usado: {}", format_bytes(total));
+
+        if let Some(free) = storage::free_space(&self.config.download_dir) {
+            let _ = write!(summary, " - Libre: {}", format_bytes(free));
+        }
+        if self.config.storage_quota_gib > 0 {
+            let _ = write!(summary, " - Cuota: {} GB", self.config.storage_quota_gib);
+        }
+
+        summary
+    }
+
+    fn targets(&self) -> Vec<&Usage> {
+        let Some(usage) = &self.usage else {
+            return vec![];
+        };
+
+        if self.marked.is_empty() {
+            usage.get(self.selected).into_iter().collect()
+        } else {
+            usage
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| self.marked.contains(i))
+                .map(|(_, entry)| entry)
+                .collect()
+        }
+    }
+
+    fn remove_targets(&self) -> Task<app::Message> {
+        let (busy, targets): (Vec<_>, Vec<_>) = self
+            .targets()
+            .into_iter()
+            .map(|entry| entry.path.clone())
+            .partition(|path| self.download.is_busy(path));
+
+        if !busy.is_empty() {
+            notifications::notify(
+                notifications::STORAGE,
+                &format!(
+                    "No se han borrado {} elementos con descargas pendientes",
+                    busy.len()
+                ),
+                vec![],
+            );
+        }
+
+        let root = self.config.download_dir.clone();
+        let download = self.download.clone();
+
+        Task::perform(
+            async move {
+                let _ = tokio::task::spawn_blocking(move || {
+                    let mut failed = 0;
+
+                    for path in targets {
+                        if let Err(err) = storage::remove(&root, &path) {
+                            eprintln!("Couldn't remove {}: {err}", path.display());
+                            failed += 1;
+                        }
+                        download.library().forget(&path);
+                    }
+
+                    if failed > 0 {
+                        notifications::notify(
+                            notifications::STORAGE,
+                            &format!("No se han podido borrar {failed} elementos"),
+                            vec![],
+                        );
+                    }
+                })
+                .await;
+            },
+            |()| app::Message::Storage(Message::Removed),
+        )
+    }
+
+    fn scroll_to_index(&self) -> Task<app::Message> {
+        let list_len = self.len();
+
+        if self.selected >= list_len {
+            return Task::none();
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let offset = self.selected as f32 / list_len as f32;
+
+        snap_to(
+            Id::new(STORAGE_SCROLLABLE_ID),
+            scrollable::RelativeOffset {
+                x: 0.0,
+                y: offset.clamp(0.0, 1.0),
+            },
+        )
+    }
+}